@@ -1,161 +1,812 @@
-use crate::globals::{NodeType, TokenType, TreeNode};
-use crate::symTab::{insert, lookup, print};
-
-// Estructura para manejar el tipo de error en los nodos
-pub fn type_error(t: &TreeNode, message: &str) {
-    println!(
-        "Type error en la línea {}: {}",
-        t.value.clone().unwrap_or_default(),
-        message
-    );
-}
-
-// Función para recorrer el árbol de sintaxis abstracta en preorden y postorden
-fn traverse(
-    t: Option<&TreeNode>,
-    pre_proc: Option<&dyn Fn(&TreeNode)>,
-    post_proc: Option<&dyn Fn(&TreeNode)>,
+use crate::globals::{
+    codes, Diagnostic, Diagnostics, EvalError, ExpType, NodeType, Span, TokenType, TreeNode, Value,
+};
+use crate::symTab::{ScopeKind, SymbolTable};
+use std::collections::{HashMap, HashSet};
+
+// Registra un error semántico en el nodo indicado
+fn type_error(t: &TreeNode, code: &str, message: &str, diagnostics: &mut Diagnostics) {
+    diagnostics.push(Diagnostic::error(code, message.to_string(), t.span.clone()));
+}
+
+// Función para recorrer el árbol de sintaxis abstracta en preorden y postorden.
+// Ambos procedimientos reciben el mismo contexto mutable.
+fn traverse<C>(
+    t: &mut TreeNode,
+    context: &mut C,
+    pre_proc: fn(&mut TreeNode, &mut C),
+    post_proc: fn(&mut TreeNode, &mut C),
 ) {
-    if let Some(node) = t {
-        if let Some(pre) = pre_proc {
-            pre(node);
-        }
+    pre_proc(t, context);
+    for child in &mut t.children {
+        traverse(child, context, pre_proc, post_proc);
+    }
+    post_proc(t, context);
+}
+
+// Procedimiento que no hace nada, para recorridos solo en preorden o solo en postorden
+fn null_proc<C>(_: &mut TreeNode, _: &mut C) {}
+
+// Nombre del tipo tal como se guarda en la tabla de símbolos
+fn type_name(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::DoubleStatement => "double",
+        _ => "int",
+    }
+}
+
+// Tipo de expresión de una variable según su tipo declarado
+fn declared_type(_type: &str) -> ExpType {
+    match _type {
+        "double" => ExpType::Double,
+        _ => ExpType::Integer,
+    }
+}
+
+// Declaración vista durante la construcción de la tabla de símbolos
+struct Declaration {
+    name: String,
+    memloc: usize,
+    span: Span,
+    read: bool,
+}
+
+// Estado del recorrido que construye la tabla de símbolos
+struct SymtabBuilder<'a> {
+    symbol_table: &'a mut SymbolTable,
+    diagnostics: &'a mut Diagnostics,
+    declarations: Vec<Declaration>,
+    // Tipo de la declaración que se está recorriendo
+    declaring: &'static str,
+    // Inicio (en bytes) de los identificadores que reciben un valor, que no
+    // cuentan como lecturas
+    targets: HashSet<usize>,
+    // Valor conocido de cada variable (por ubicación de memoria) en el punto
+    // del recorrido, solo mientras es seguro en toda ejecución
+    known: HashMap<usize, Value>,
+    // Sentencias de control que encierran al nodo actual; dentro de ellas una
+    // asignación no siempre se ejecuta
+    control_depth: usize,
+    // Inicio (en bytes) de los enteros precedidos de '-', que pueden llegar a
+    // -9223372036854775808 aunque sin el signo no quepan en un int
+    negated_literals: HashSet<usize>,
+}
+
+impl SymtabBuilder<'_> {
+    fn declaration(&self, memloc: usize) -> Option<&Declaration> {
+        self.declarations.iter().find(|d| d.memloc == memloc)
+    }
+}
+
+// Texto de una expresión reconstruido desde el árbol
+fn expression_text(t: &TreeNode) -> String {
+    match t.children.len() {
+        0 => t.value.clone().unwrap_or_default(),
+        // ( expresión )
+        1 => format!("({})", expression_text(&t.children[0])),
+        // Operador unario
+        2 => format!(
+            "{}{}",
+            t.children[0].value.clone().unwrap_or_default(),
+            expression_text(&t.children[1])
+        ),
+        _ => format!(
+            "{} {} {}",
+            expression_text(&t.children[0]),
+            t.children[1].value.clone().unwrap_or_default(),
+            expression_text(&t.children[2])
+        ),
+    }
+}
 
-        for child in &node.children {
-            traverse(Some(child), pre_proc, post_proc);
+// Declara una variable en el ámbito actual; puede ocultar a una de un ámbito externo
+fn declare(builder: &mut SymtabBuilder, t: &mut TreeNode, _type: &str) {
+    let name = match t.value.clone() {
+        Some(name) => name,
+        None => return,
+    };
+    t.exp_type = declared_type(_type);
+    if let Some(original) = builder.symbol_table.lookup_current(&name) {
+        let mut diagnostic = Diagnostic::error(
+            codes::REDECLARED_VARIABLE,
+            format!("La variable '{}' ya está declarada en este ámbito", name),
+            t.span.clone(),
+        );
+        if let Some(declaration) = builder.declaration(original.memloc) {
+            diagnostic =
+                diagnostic.with_label(declaration.span.clone(), "declarada aquí".to_string());
         }
+        builder.diagnostics.push(diagnostic);
+        return;
+    }
+    // La columna de valor guarda el inicializador, o 0 si no tiene
+    let value = t
+        .children
+        .first()
+        .map(expression_text)
+        .unwrap_or_else(|| "0".to_string());
+    let loc = builder.symbol_table.next_location();
+    builder
+        .symbol_table
+        .insert(&name, _type, &value, t.span.line, loc);
+    builder
+        .symbol_table
+        .bind_declaration(t.span.start_byte, loc);
+    builder.declarations.push(Declaration {
+        name,
+        memloc: loc,
+        span: t.span.clone(),
+        read: false,
+    });
+}
 
-        if let Some(post) = post_proc {
-            post(node);
+// Uso de una variable: agrega su línea, fija su tipo (al verificar tipos los
+// ámbitos ya están cerrados) y la marca como leída si no se le asigna un valor
+fn use_variable(builder: &mut SymtabBuilder, t: &mut TreeNode) {
+    let name = match t.value {
+        Some(ref name) => name,
+        None => return,
+    };
+    let bucket = match builder.symbol_table.lookup(name) {
+        Some(bucket) => bucket,
+        None => {
+            let mut diagnostic = Diagnostic::error(
+                codes::UNDECLARED_VARIABLE,
+                format!("La variable '{}' no está declarada", name),
+                t.span.clone(),
+            )
+            .with_help(format!("declárala antes de usarla: int {};", name));
+            // Puede que exista pero solo dentro de un bloque ya cerrado
+            if let Some(declaration) = builder.declarations.iter().find(|d| &d.name == name) {
+                diagnostic = diagnostic.with_label(
+                    declaration.span.clone(),
+                    format!("'{}' solo existe dentro de su bloque", name),
+                );
+            }
+            builder.diagnostics.push(diagnostic);
+            return;
         }
+    };
+    t.exp_type = declared_type(&bucket._type);
+    let memloc = bucket.memloc;
+    builder.symbol_table.add_line(name, t.span.line);
+    if !builder.targets.contains(&t.span.start_byte) {
+        if let Some(declaration) = builder.declarations.iter_mut().find(|d| d.memloc == memloc) {
+            declaration.read = true;
+        }
+        t.constant = builder.known.get(&memloc).copied();
     }
 }
 
-// Función para insertar nodos en la tabla de símbolos
-fn insert_node(t: &TreeNode, symbol_table: &mut SymbolTable) {
+// Sentencias cuyo cuerpo puede ejecutarse cero o varias veces, o solo en algunas ramas
+fn is_control_statement(node_type: &NodeType) -> bool {
+    matches!(
+        node_type,
+        NodeType::IfStatement
+            | NodeType::WhileStatement
+            | NodeType::DoWhileStatement
+            | NodeType::ForStatement
+            | NodeType::RepeatUntilStatement
+            | NodeType::SwitchStatement
+    )
+}
+
+// Nombres de las variables que reciben un valor dentro de una sentencia
+fn assigned_names(t: &TreeNode, names: &mut Vec<String>) {
     match t.node_type {
-        // Maneja declaraciones de variables (integer y double)
-        NodeType::IntStatement | NodeType::DoubleStatement => {
-            if let Some(ref name) = t.value {
-                let lineno = t.token.unwrap() as usize;
+        NodeType::Assignment
+        | NodeType::CinStatement
+        | NodeType::ReadStatement
+        | NodeType::Increment
+        | NodeType::Decrement => {
+            if let Some(name) = t.children.first().and_then(|target| target.value.clone()) {
+                names.push(name);
+            }
+        }
+        _ => {}
+    }
+    for child in &t.children {
+        assigned_names(child, names);
+    }
+}
+
+// Olvida el valor conocido de las variables asignadas dentro de la sentencia
+fn forget_assigned(builder: &mut SymtabBuilder, t: &TreeNode) {
+    let mut names = Vec::new();
+    assigned_names(t, &mut names);
+    for name in names {
+        if let Some(bucket) = builder.symbol_table.lookup(&name) {
+            builder.known.remove(&bucket.memloc);
+        }
+    }
+}
+
+// Registra el valor que recibe una variable: se conoce solo si es constante y la
+// asignación se ejecuta siempre
+fn assign_known(
+    builder: &mut SymtabBuilder,
+    memloc: usize,
+    exp_type: ExpType,
+    value: Option<Value>,
+) {
+    let value = value.and_then(|value| value.cast(exp_type));
+    match value {
+        Some(value) if builder.control_depth == 0 => {
+            builder.known.insert(memloc, value);
+        }
+        _ => {
+            builder.known.remove(&memloc);
+        }
+    }
+}
 
+// Calcula el valor de una operación binaria [izquierdo, operador, derecho] con
+// operandos constantes. La división entre un cero constante es un error aunque
+// el dividendo no sea constante.
+fn fold_binary(t: &mut TreeNode, diagnostics: &mut Diagnostics) {
+    let operator = t.children[1].token.clone();
+    let divisor = &t.children[2];
+    if matches!(operator, Some(TokenType::DIVIDE | TokenType::MODULO))
+        && matches!(divisor.constant, Some(value) if value.is_zero())
+    {
+        diagnostics.push(
+            Diagnostic::error(
+                codes::DIVISION_BY_ZERO,
+                "División entre cero".to_string(),
+                t.span.clone(),
+            )
+            .with_label(
+                divisor.span.clone(),
+                "este divisor siempre vale 0".to_string(),
+            ),
+        );
+        return;
+    }
+    if let (Some(operator), Some(left), Some(right)) =
+        (operator, t.children[0].constant, t.children[2].constant)
+    {
+        let result = Value::binary(&operator, left, right);
+        t.constant = folded(result, &t.children[1], diagnostics);
+    }
+}
+
+// Valor plegado de una operación. Si el programa fallaría al ejecutarla (división
+// entre cero o desbordamiento) se reporta en el operador; una mezcla de tipos ya
+// la reporta la verificación de tipos.
+fn folded(
+    result: Result<Value, EvalError>,
+    operator: &TreeNode,
+    diagnostics: &mut Diagnostics,
+) -> Option<Value> {
+    let (code, message) = match result {
+        Ok(value) => return Some(value),
+        Err(EvalError::DivisionByZero) => (codes::DIVISION_BY_ZERO, "División entre cero"),
+        Err(EvalError::Overflow) => (codes::CONSTANT_OVERFLOW, "El resultado no cabe en un int"),
+        Err(EvalError::InvalidOperands) => return None,
+    };
+    diagnostics.push(Diagnostic::error(
+        code,
+        message.to_string(),
+        operator.span.clone(),
+    ));
+    None
+}
 
-                // Inserta en la tabla de símbolos si no está
-                let loc = symbol_table.lookup(name).unwrap_or_else(|| {
-                    let new_loc = symbol_table.next_location();
-                    symbol_table.insert(name, t.token, "0", lineno, new_loc);
-                    new_loc
-                });
+// Valor de un entero precedido de '-' que sin el signo no cabe en un int
+fn negated_literal(t: &TreeNode, builder: &SymtabBuilder) -> Option<Value> {
+    if !builder.negated_literals.contains(&t.span.start_byte) {
+        return None;
+    }
+    let lexeme = t.value.as_deref().unwrap_or_default();
+    format!("-{}", lexeme).parse().ok().map(Value::Int)
+}
 
-                // Actualiza el símbolo con la línea de uso
-                symbol_table.insert(name, t.token, "0", lineno, loc);
+// Propaga los valores constantes en postorden, después de visitar los hijos
+fn fold_node(t: &mut TreeNode, builder: &mut SymtabBuilder) {
+    match t.node_type {
+        NodeType::Factor => match t.token {
+            Some(TokenType::NumInt | TokenType::NumReal | TokenType::TRUE | TokenType::FALSE) => {
+                let lexeme = t.value.clone().unwrap_or_default();
+                t.constant = t
+                    .token
+                    .as_ref()
+                    .and_then(|token| Value::from_literal(token, &lexeme));
+                // Solo un entero demasiado grande no tiene valor; si lleva signo menos
+                // el valor lo calcula la expresión unaria
+                if t.constant.is_none() && negated_literal(t, builder).is_none() {
+                    builder.diagnostics.push(
+                        Diagnostic::error(
+                            codes::NUMBER_OUT_OF_RANGE,
+                            format!("El número {} no cabe en un int", lexeme),
+                            t.span.clone(),
+                        )
+                        .with_help(format!("el mayor int es {}", i64::MAX)),
+                    );
+                }
+            }
+            // ( expresión )
+            None => t.constant = t.children.first().and_then(|child| child.constant),
+            _ => {}
+        },
+        NodeType::UnaryExpression => {
+            if let (Some(operator), Some(operand)) = (&t.children[0].token, t.children[1].constant)
+            {
+                let result = Value::unary(operator, operand);
+                t.constant = folded(result, &t.children[0], builder.diagnostics);
+            } else {
+                t.constant = negated_literal(&t.children[1], builder);
+            }
+        }
+        NodeType::Expression | NodeType::Term => fold_binary(t, builder.diagnostics),
+        NodeType::Declarator => {
+            // Solo si este declarador es el que quedó registrado (no una redeclaración)
+            let memloc = match builder
+                .declarations
+                .iter()
+                .find(|d| d.span.start_byte == t.span.start_byte)
+            {
+                Some(declaration) => declaration.memloc,
+                None => return,
+            };
+            let initializer = t.children.first().and_then(|child| child.constant);
+            assign_known(builder, memloc, t.exp_type, initializer);
+            // La columna de valor muestra el inicializador ya calculado
+            if let (Some(value), Some(name)) =
+                (builder.known.get(&memloc).copied(), t.value.as_ref())
+            {
+                builder.symbol_table.set_value(name, &value.to_string());
+            }
+        }
+        NodeType::Assignment => {
+            let target = &t.children[0];
+            if let Some(bucket) = target
+                .value
+                .as_ref()
+                .and_then(|name| builder.symbol_table.lookup(name))
+            {
+                let memloc = bucket.memloc;
+                assign_known(builder, memloc, target.exp_type, t.children[1].constant);
             }
         }
+        NodeType::CinStatement | NodeType::ReadStatement => forget_assigned(builder, t),
+        NodeType::Increment | NodeType::Decrement => {
+            let target = &t.children[0];
+            let step = if t.node_type == NodeType::Increment {
+                TokenType::PLUS
+            } else {
+                TokenType::MINUS
+            };
+            if let Some(bucket) = target
+                .value
+                .as_ref()
+                .and_then(|name| builder.symbol_table.lookup(name))
+            {
+                let memloc = bucket.memloc;
+                let value = target
+                    .constant
+                    .and_then(|value| Value::binary(&step, value, Value::Int(1)).ok());
+                assign_known(builder, memloc, target.exp_type, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Función para insertar nodos en la tabla de símbolos
+fn insert_node(t: &mut TreeNode, builder: &mut SymtabBuilder) {
+    match t.node_type {
+        // main y cada bloque abren un ámbito nuevo
+        NodeType::MainFunction => builder.symbol_table.enter_scope(ScopeKind::Function),
+        NodeType::Block => builder.symbol_table.enter_scope(ScopeKind::Block),
+
+        // Maneja declaraciones de variables (integer y double). Cada declarador
+        // se registra al visitarlo, así su inicializador ve a los anteriores.
+        NodeType::IntStatement | NodeType::DoubleStatement => {
+            builder.declaring = type_name(&t.node_type);
+        }
+        NodeType::Declarator => {
+            let _type = builder.declaring;
+            declare(builder, t, _type);
+        }
 
-        // para asignaciones
-        NodeType::Assignment=> {
-            if let Some(ref name) = t.value {
-                let lineno = t.token.unwrap() as usize;
+        // El identificador que recibe el valor no es una lectura
+        NodeType::Assignment | NodeType::CinStatement | NodeType::ReadStatement => {
+            if let Some(target) = t.children.first() {
+                builder.targets.insert(target.span.start_byte);
+            }
+        }
 
-                // Inserta en la tabla de símbolos si no está
-                let loc = symbol_table.lookup(name).unwrap_or_else(|| {
-                    let new_loc = symbol_table.next_location();
-                    symbol_table.insert(name, t.token, t.value, lineno, new_loc);
-                    new_loc
-                });
+        NodeType::Factor if t.token == Some(TokenType::ID) => {
+            use_variable(builder, t);
+        }
 
-                //en el caso de las asignaciones si no encuentra la variable a la cual le estamos asignando valor, entonces marcamos error y no asignamos
+        NodeType::UnaryExpression
+            if t.children[0].token == Some(TokenType::MINUS)
+                && t.children[1].token == Some(TokenType::NumInt) =>
+        {
+            builder
+                .negated_literals
+                .insert(t.children[1].span.start_byte);
+        }
 
+        // En un ciclo, la condición y el cuerpo pueden ver valores asignados en la
+        // vuelta anterior
+        _ if is_control_statement(&t.node_type) => {
+            if t.node_type != NodeType::IfStatement && t.node_type != NodeType::SwitchStatement {
+                forget_assigned(builder, t);
+            }
+            builder.control_depth += 1;
+            // El for abre un ámbito: lo declarado en su inicialización solo existe en el ciclo
+            if t.node_type == NodeType::ForStatement {
+                builder.symbol_table.enter_scope(ScopeKind::For);
             }
         }
+        _ => {}
     }
 }
 
-// Procedimiento que construye la tabla de símbolos recorriendo el AST en preorden
-pub fn build_symtab(syntax_tree: &TreeNode, symbol_table: &mut SymbolTable) {
-    traverse(
-        Some(syntax_tree),
-        Some(&|node| insert_node(node, symbol_table)),
-        None,
-    );
-    symbol_table.print();
+// Calcula las constantes del nodo y cierra el ámbito al salir de main, de un bloque o de un for
+fn exit_node(t: &mut TreeNode, builder: &mut SymtabBuilder) {
+    fold_node(t, builder);
+    if matches!(
+        t.node_type,
+        NodeType::MainFunction | NodeType::Block | NodeType::ForStatement
+    ) {
+        builder.symbol_table.exit_scope();
+    }
+    if is_control_statement(&t.node_type) {
+        builder.control_depth -= 1;
+    }
 }
 
-fn check_node(t: &TreeNode) {
-    match t.node_type {
-        NodeType::Expression => {
-            if let Some(token) = &t.token {
-                match token {
-                    TokenType::PLUS
-                    | TokenType::MINUS
-                    | TokenType::TIMES
-                    | TokenType::DIVIDE
-                    | TokenType::MODULO
-                    | TokenType::POWER => {
-                        let left_type = &t.children[0].node_type;
-                        let right_type = &t.children[1].node_type;
-
-                        // Verificar que los operandos sean expresiones válidas
-                        if left_type != &NodeType::Expression || right_type != &NodeType::Expression
-                        {
-                            type_error(t, "Operador aplicado a no números");
-                        }
-
-                        // Verificación de operadores relacionales
-                        if matches!(
-                            t.token,
-                            Some(TokenType::EQ)
-                                | Some(TokenType::NEQ)
-                                | Some(TokenType::LT)
-                                | Some(TokenType::LTE)
-                                | Some(TokenType::GT)
-                                | Some(TokenType::GTE)
-                        ) {
-                            // Si es un operador relacional, establecemos el tipo de nodo como Error
-                            t.borrow_mut().node_type = NodeType::Error;
-                        }
-                    }
-                    _ => {}
-                }
+// Procedimiento que construye la tabla de símbolos recorriendo el AST en preorden.
+// Reporta variables sin declarar, declaradas dos veces o que nunca se leen. De
+// paso pliega las expresiones constantes y reporta las divisiones entre cero y los
+// desbordamientos.
+pub fn build_symtab(
+    syntax_tree: &mut TreeNode,
+    symbol_table: &mut SymbolTable,
+    diagnostics: &mut Diagnostics,
+) {
+    let mut builder = SymtabBuilder {
+        symbol_table,
+        diagnostics,
+        declarations: Vec::new(),
+        declaring: "int",
+        targets: HashSet::new(),
+        known: HashMap::new(),
+        control_depth: 0,
+        negated_literals: HashSet::new(),
+    };
+    traverse(syntax_tree, &mut builder, insert_node, exit_node);
+    for declaration in builder.declarations.iter().filter(|d| !d.read) {
+        builder.diagnostics.push(Diagnostic::warning(
+            codes::UNUSED_VARIABLE,
+            format!("La variable '{}' nunca se lee", declaration.name),
+            declaration.span.clone(),
+        ));
+    }
+}
+
+fn is_numeric(exp_type: ExpType) -> bool {
+    matches!(exp_type, ExpType::Integer | ExpType::Double)
+}
+
+// Tipo de una operación aritmética: double si cualquiera de los operandos lo es
+// (el entero se promueve implícitamente a double)
+fn arithmetic_type(left: ExpType, right: ExpType) -> ExpType {
+    if left == ExpType::Double || right == ExpType::Double {
+        ExpType::Double
+    } else {
+        ExpType::Integer
+    }
+}
+
+// Anota el tipo de una operación binaria [izquierdo, operador, derecho]
+fn check_binary(t: &mut TreeNode, diagnostics: &mut Diagnostics) {
+    let left = t.children[0].exp_type;
+    let right = t.children[2].exp_type;
+    let operator = t.children[1].token.clone();
+    // Un operando sin tipo ya tiene su propio error
+    let known = left != ExpType::Void && right != ExpType::Void;
+    match operator {
+        Some(
+            TokenType::PLUS
+            | TokenType::MINUS
+            | TokenType::TIMES
+            | TokenType::DIVIDE
+            | TokenType::MODULO
+            | TokenType::POWER,
+        ) => {
+            if known && (!is_numeric(left) || !is_numeric(right)) {
+                type_error(
+                    t,
+                    codes::TYPE_MISMATCH,
+                    "Operador aritmético aplicado a no números",
+                    diagnostics,
+                );
+            }
+            // El residuo solo está definido entre enteros
+            if operator == Some(TokenType::MODULO)
+                && (left == ExpType::Double || right == ExpType::Double)
+            {
+                diagnostics.push(
+                    Diagnostic::error(
+                        codes::DOUBLE_MODULO,
+                        "Operador '%' aplicado a un double".to_string(),
+                        t.span.clone(),
+                    )
+                    .with_help("'%' solo acepta operandos int".to_string()),
+                );
             }
+            t.exp_type = arithmetic_type(left, right);
         }
-        NodeType::IfStatement => {
-            // Verificación de que la condición del if sea una expresión válida
-            if t.children[0].node_type != NodeType::Expression {
-                type_error(&t.children[0], "La condición del 'if' no es booleana");
+        Some(TokenType::LT | TokenType::LTE | TokenType::GT | TokenType::GTE) => {
+            if known && (!is_numeric(left) || !is_numeric(right)) {
+                type_error(
+                    t,
+                    codes::TYPE_MISMATCH,
+                    "Operador relacional aplicado a no números",
+                    diagnostics,
+                );
             }
+            t.exp_type = ExpType::Boolean;
         }
-        NodeType::Assignment => {
-            // Verificación de que la asignación sea de un valor válido
-            if t.children[0].node_type != NodeType::Expression {
-                type_error(&t.children[0], "Asignación de valor no válido");
+        Some(TokenType::EQ | TokenType::NEQ) => {
+            if known && is_numeric(left) != is_numeric(right) {
+                type_error(
+                    t,
+                    codes::TYPE_MISMATCH,
+                    "Comparación entre un número y un booleano",
+                    diagnostics,
+                );
+            }
+            t.exp_type = ExpType::Boolean;
+        }
+        Some(TokenType::AND | TokenType::OR) => {
+            if known && (left != ExpType::Boolean || right != ExpType::Boolean) {
+                type_error(
+                    t,
+                    codes::TYPE_MISMATCH,
+                    "Operador lógico aplicado a no booleanos",
+                    diagnostics,
+                );
             }
+            t.exp_type = ExpType::Boolean;
         }
-        NodeType::WriteStatement => {
-            // Verificación de que el valor a escribir sea válido
-            if t.children[0].node_type != NodeType::Expression {
-                type_error(&t.children[0], "Escritura de valor no válido");
+        _ => {}
+    }
+}
+
+// Verifica que el valor asignado quepa en la variable: int se promueve a double,
+// pero un double no se trunca a int ni un booleano se guarda en una variable numérica
+fn check_assignment(t: &mut TreeNode, diagnostics: &mut Diagnostics) {
+    // x = valor, o un declarador int x = valor cuyo único hijo es el valor
+    let (target_node, value_node) = match t.node_type {
+        NodeType::Declarator => match t.children.first() {
+            Some(value_node) => (&*t, value_node),
+            None => return,
+        },
+        _ => (&t.children[0], &t.children[1]),
+    };
+    let target = target_node.exp_type;
+    let value = value_node.exp_type;
+    let name = target_node.value.clone().unwrap_or_default();
+    let target_span = target_node.span.clone();
+    let value_span = value_node.span.clone();
+    t.exp_type = target;
+
+    if target == ExpType::Void || value == ExpType::Void || value == target {
+        return;
+    }
+    let message = match (target, value) {
+        (ExpType::Double, ExpType::Integer) => return,
+        (ExpType::Integer, ExpType::Double) => format!(
+            "No se puede asignar un valor double a la variable int '{}'",
+            name
+        ),
+        _ => format!(
+            "No se puede asignar un valor {} a la variable '{}'",
+            type_label(value),
+            name
+        ),
+    };
+    let mut diagnostic = Diagnostic::error(codes::INCOMPATIBLE_ASSIGNMENT, message, value_span)
+        .with_label(target_span, format!("'{}' es {}", name, type_label(target)));
+    if target == ExpType::Integer && value == ExpType::Double {
+        diagnostic = diagnostic.with_help(format!("declara '{}' como double", name));
+    }
+    diagnostics.push(diagnostic);
+}
+
+// Nombre del tipo en los mensajes
+fn type_label(exp_type: ExpType) -> &'static str {
+    match exp_type {
+        ExpType::Integer => "int",
+        ExpType::Double => "double",
+        ExpType::Boolean => "booleano",
+        ExpType::Void => "sin tipo",
+    }
+}
+
+// Verifica que la condición de una sentencia sea booleana
+fn check_condition(condition: &TreeNode, statement: &str, diagnostics: &mut Diagnostics) {
+    // Como en C, un int también sirve de condición: se cumple si no es 0
+    if !matches!(
+        condition.exp_type,
+        ExpType::Boolean | ExpType::Integer | ExpType::Void
+    ) {
+        type_error(
+            condition,
+            codes::NON_BOOLEAN_CONDITION,
+            &format!("La condición del '{}' no es booleana", statement),
+            diagnostics,
+        );
+    }
+}
+
+fn check_node(t: &mut TreeNode, diagnostics: &mut Diagnostics) {
+    match t.node_type {
+        NodeType::Factor => {
+            t.exp_type = match t.token {
+                Some(TokenType::NumInt) => ExpType::Integer,
+                Some(TokenType::NumReal) => ExpType::Double,
+                Some(TokenType::TRUE | TokenType::FALSE) => ExpType::Boolean,
+                // Anotado por build_symtab
+                Some(TokenType::ID) => t.exp_type,
+                // ( expresión )
+                None => t
+                    .children
+                    .first()
+                    .map(|child| child.exp_type)
+                    .unwrap_or(ExpType::Void),
+                _ => ExpType::Void,
+            };
+        }
+        NodeType::Expression | NodeType::Term => check_binary(t, diagnostics),
+        NodeType::Assignment | NodeType::Declarator => check_assignment(t, diagnostics),
+        NodeType::UnaryExpression => {
+            let operand = t.children[1].exp_type;
+            if t.children[0].token == Some(TokenType::NOT) {
+                if operand != ExpType::Boolean && operand != ExpType::Void {
+                    type_error(
+                        t,
+                        codes::TYPE_MISMATCH,
+                        "Operador '!' aplicado a un no booleano",
+                        diagnostics,
+                    );
+                }
+                t.exp_type = ExpType::Boolean;
+            } else {
+                if operand == ExpType::Boolean {
+                    type_error(
+                        t,
+                        codes::TYPE_MISMATCH,
+                        "Signo aplicado a un booleano",
+                        diagnostics,
+                    );
+                }
+                t.exp_type = operand;
             }
         }
+        NodeType::IfStatement | NodeType::WhileStatement => {
+            let statement = if t.node_type == NodeType::IfStatement {
+                "if"
+            } else {
+                "while"
+            };
+            check_condition(&t.children[0], statement, diagnostics);
+        }
         NodeType::DoWhileStatement => {
             // Verificación de que la condición del do-while sea booleana
-            if t.children[1].node_type != NodeType::Expression {
-                type_error(&t.children[1], "La condición del 'do-while' no es booleana");
-            }
+            check_condition(&t.children[1], "do-while", diagnostics);
+        }
+        NodeType::ForStatement => {
+            // Verificación de que la condición del for sea booleana
+            // (la inicialización y la actualización se revisan como declaraciones o asignaciones)
+            check_condition(&t.children[1], "for", diagnostics);
         }
         NodeType::RepeatUntilStatement => {
             // Verificación de que la condición del repeat-until sea booleana
-            if t.children[1].node_type != NodeType::Expression {
+            check_condition(&t.children[1], "repeat-until", diagnostics);
+        }
+        NodeType::SwitchStatement => {
+            // Verificación de que el selector del switch sea entero
+            let selector = &t.children[0];
+            if selector.exp_type != ExpType::Integer && selector.exp_type != ExpType::Void {
                 type_error(
-                    &t.children[1],
-                    "La condición del 'repeat-until' no es booleana",
+                    selector,
+                    codes::NON_INTEGER_SWITCH,
+                    "El selector del 'switch' no es entero",
+                    diagnostics,
                 );
             }
+            // Verificación de etiquetas de case enteras y sin repetir, y de un solo default
+            let mut labels: Vec<Value> = Vec::new();
+            let mut has_default = false;
+            for case in &t.children[1..] {
+                if case.node_type == NodeType::DefaultStatement {
+                    if has_default {
+                        type_error(
+                            case,
+                            codes::DUPLICATE_CASE_LABEL,
+                            "'default' duplicado",
+                            diagnostics,
+                        );
+                    }
+                    has_default = true;
+                    continue;
+                }
+                if case.node_type != NodeType::CaseStatement {
+                    continue;
+                }
+                let label = &case.children[0];
+                if label.exp_type != ExpType::Integer {
+                    type_error(
+                        label,
+                        codes::NON_INTEGER_SWITCH,
+                        "La etiqueta del 'case' no es entera",
+                        diagnostics,
+                    );
+                    continue;
+                }
+                // Una etiqueta fuera de rango ya se reportó al plegar las constantes
+                // y no se compara con las demás
+                let value = match label.constant {
+                    Some(value) => value,
+                    None => continue,
+                };
+                if labels.contains(&value) {
+                    type_error(
+                        label,
+                        codes::DUPLICATE_CASE_LABEL,
+                        "Etiqueta de 'case' duplicada",
+                        diagnostics,
+                    );
+                } else {
+                    labels.push(value);
+                }
+            }
         }
         _ => {}
     }
 }
-// Procedimiento para realizar la verificación de tipos
-pub fn type_check(syntax_tree: Option<&TreeNode>) {
-    traverse(syntax_tree, None, Some(&check_node));
+
+// Verifica que cada 'break' esté dentro de un ciclo o de un switch
+fn check_breaks(t: &TreeNode, breakable: bool, diagnostics: &mut Diagnostics) {
+    if t.node_type == NodeType::BreakStatement && !breakable {
+        type_error(
+            t,
+            codes::BREAK_OUTSIDE_LOOP,
+            "'break' fuera de un ciclo o de un switch",
+            diagnostics,
+        );
+    }
+    let breakable = breakable
+        || matches!(
+            t.node_type,
+            NodeType::WhileStatement
+                | NodeType::DoWhileStatement
+                | NodeType::ForStatement
+                | NodeType::RepeatUntilStatement
+                | NodeType::SwitchStatement
+        );
+    for child in &t.children {
+        check_breaks(child, breakable, diagnostics);
+    }
+}
+
+// Procedimiento para realizar la verificación de tipos recorriendo el AST en postorden
+pub fn type_check(syntax_tree: &mut TreeNode, diagnostics: &mut Diagnostics) {
+    traverse(syntax_tree, diagnostics, null_proc, check_node);
+    check_breaks(syntax_tree, false, diagnostics);
+}
+
+// Análisis semántico completo: tabla de símbolos y verificación de tipos.
+// El árbol queda anotado con el tipo de cada expresión y, si es constante, su valor.
+pub fn analyze(syntax_tree: &mut TreeNode, diagnostics: &mut Diagnostics) -> SymbolTable {
+    let mut symbol_table = SymbolTable::new();
+    build_symtab(syntax_tree, &mut symbol_table, diagnostics);
+    type_check(syntax_tree, diagnostics);
+    symbol_table
 }
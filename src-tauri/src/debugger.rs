@@ -0,0 +1,247 @@
+use crate::globals::{ExpType, Span, TreeNode, Value};
+use crate::interpreter::{Observer, Variable};
+use crate::symTab::SymbolTable;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+// Líneas con breakpoint; se comparten para poder cambiarlas mientras el programa corre
+pub type Breakpoints = Arc<Mutex<HashSet<usize>>>;
+
+// Orden del usuario para reanudar un programa detenido. Como el lenguaje no tiene
+// funciones, entrar, saltar y salir se miden en sentencias anidadas: saltar no se
+// detiene dentro del cuerpo de un if o de un ciclo, y salir corre hasta terminar
+// la sentencia que encierra a la actual.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugCommand {
+    Continue,
+    StepInto,
+    StepOver,
+    StepOut,
+}
+
+// Motivo por el que se detuvo el programa
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum PauseReason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+// Variable visible al detenerse, con su ubicación en la tabla de símbolos
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct VariableState {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub value: Value,
+    pub memloc: Option<usize>,
+    // Ámbito abierto donde vive, contando desde el más externo (0)
+    pub scope_depth: usize,
+}
+
+// Estado del programa detenido antes de ejecutar la sentencia de `span`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PausedState {
+    pub reason: PauseReason,
+    pub span: Span,
+    pub variables: Vec<VariableState>,
+}
+
+// Aviso del depurador hacia quien lo muestra
+#[derive(Debug, PartialEq, Clone)]
+pub enum DebugEvent {
+    Paused(PausedState),
+    Resumed,
+}
+
+// Hasta dónde correr antes de volver a detenerse
+enum StepMode {
+    Entry,
+    Continue,
+    Into,
+    Over(usize),
+    Out(usize),
+}
+
+// Sentencia o condición desde la que se reanudó: las sentencias anidadas en ella
+// que están en la misma línea no vuelven a detenerse en su breakpoint
+struct Resumed {
+    line: usize,
+    depth: usize,
+    // Ya se evaluó una condición de ciclo en esta pasada por la línea
+    condition_seen: bool,
+}
+
+// Depurador: detiene al intérprete en los breakpoints y al avanzar paso a paso,
+// y espera la siguiente orden en un canal
+pub struct Debugger<F: FnMut(DebugEvent)> {
+    symbol_table: SymbolTable,
+    breakpoints: Breakpoints,
+    commands: Receiver<DebugCommand>,
+    emit: F,
+    mode: StepMode,
+    resumed_at: Option<Resumed>,
+    // Ya no llegan órdenes: el programa corre sin detenerse
+    detached: bool,
+}
+
+impl<F: FnMut(DebugEvent)> Debugger<F> {
+    pub fn new(
+        symbol_table: SymbolTable,
+        breakpoints: Breakpoints,
+        commands: Receiver<DebugCommand>,
+        stop_on_entry: bool,
+        emit: F,
+    ) -> Self {
+        Debugger {
+            symbol_table,
+            breakpoints,
+            commands,
+            emit,
+            mode: if stop_on_entry {
+                StepMode::Entry
+            } else {
+                StepMode::Continue
+            },
+            resumed_at: None,
+            detached: false,
+        }
+    }
+
+    fn pause_reason(&self, statement: &TreeNode, depth: usize) -> Option<PauseReason> {
+        let line = statement.span.line;
+        match self.mode {
+            StepMode::Entry => return Some(PauseReason::Entry),
+            StepMode::Into => return Some(PauseReason::Step),
+            StepMode::Over(from) if depth <= from => return Some(PauseReason::Step),
+            StepMode::Out(from) if depth < from => return Some(PauseReason::Step),
+            _ => {}
+        }
+        if self.at_breakpoint(line) && self.resumed_at.is_none() {
+            Some(PauseReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+
+    fn at_breakpoint(&self, line: usize) -> bool {
+        self.breakpoints
+            .lock()
+            .map(|breakpoints| breakpoints.contains(&line))
+            .unwrap_or(false)
+    }
+
+    // Avisa que el programa se detuvo en `span` y espera la orden para reanudarlo.
+    // Los pasos se miden desde `step_depth`.
+    fn pause(
+        &mut self,
+        reason: PauseReason,
+        span: &Span,
+        frames: &[Vec<Variable>],
+        step_depth: usize,
+        resumed: Resumed,
+    ) {
+        let variables = self.variables(frames);
+        (self.emit)(DebugEvent::Paused(PausedState {
+            reason,
+            span: span.clone(),
+            variables,
+        }));
+        match self.commands.recv() {
+            Ok(command) => {
+                self.mode = match command {
+                    DebugCommand::Continue => StepMode::Continue,
+                    DebugCommand::StepInto => StepMode::Into,
+                    DebugCommand::StepOver => StepMode::Over(step_depth),
+                    DebugCommand::StepOut => StepMode::Out(step_depth),
+                };
+                self.resumed_at = Some(resumed);
+                (self.emit)(DebugEvent::Resumed);
+            }
+            // Se cerró la sesión (p. ej. al cancelar): no se vuelve a detener
+            Err(_) => self.detached = true,
+        }
+    }
+
+    // Variables de todos los ámbitos abiertos, del global al más interno
+    fn variables(&self, frames: &[Vec<Variable>]) -> Vec<VariableState> {
+        frames
+            .iter()
+            .enumerate()
+            .flat_map(|(scope_depth, frame)| {
+                frame.iter().map(move |variable| VariableState {
+                    name: variable.name.clone(),
+                    _type: match variable.exp_type {
+                        ExpType::Double => "double".to_string(),
+                        _ => "int".to_string(),
+                    },
+                    value: variable.value,
+                    memloc: self
+                        .symbol_table
+                        .declaration_location(variable.declared_at.start_byte),
+                    scope_depth,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<F: FnMut(DebugEvent)> Observer for Debugger<F> {
+    fn before_statement(&mut self, statement: &TreeNode, depth: usize, frames: &[Vec<Variable>]) {
+        if self.detached {
+            return;
+        }
+        let line = statement.span.line;
+        if let Some(resumed) = &self.resumed_at {
+            if resumed.line != line || depth <= resumed.depth {
+                self.resumed_at = None;
+            }
+        }
+        let reason = match self.pause_reason(statement, depth) {
+            Some(reason) => reason,
+            None => return,
+        };
+        let resumed = Resumed {
+            line,
+            depth,
+            condition_seen: false,
+        };
+        self.pause(reason, &statement.span, frames, depth, resumed);
+    }
+
+    // El breakpoint de la línea de un ciclo se detiene en cada vuelta
+    fn before_condition(&mut self, condition: &TreeNode, depth: usize, frames: &[Vec<Variable>]) {
+        if self.detached {
+            return;
+        }
+        let line = condition.span.line;
+        match &mut self.resumed_at {
+            // La primera evaluación después de detenerse en el ciclo es de la
+            // misma pasada; la siguiente ya empieza otra vuelta
+            Some(resumed) if resumed.line == line && !resumed.condition_seen => {
+                resumed.condition_seen = true;
+                return;
+            }
+            _ => self.resumed_at = None,
+        }
+        if !self.at_breakpoint(line) {
+            return;
+        }
+        let resumed = Resumed {
+            line,
+            depth,
+            condition_seen: true,
+        };
+        // Los pasos se miden desde el cuerpo del ciclo
+        self.pause(
+            PauseReason::Breakpoint,
+            &condition.span,
+            frames,
+            depth + 1,
+            resumed,
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use ::lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::collections::HashSet;
+use std::fmt;
 
 // Escáner /////////////////////////////////////////////////////////////////////////////////////////
 
@@ -30,6 +30,7 @@ pub enum TokenType {
     ELSE,
     DO,
     WHILE,
+    FOR,
     REPEAT,
     UNTIL,
     READ,
@@ -37,11 +38,18 @@ pub enum TokenType {
     INTEGER,
     DOUBLE,
     MAIN,
-    AND,
-    OR,
+    AND, // && (se evalúa en cortocircuito)
+    OR,  // || (se evalúa en cortocircuito)
+    NOT,
+    TRUE,
+    FALSE,
     RETURN,
     CIN,
     COUT,
+    SWITCH,
+    CASE,
+    DEFAULT,
+    BREAK,
 
     // Tokens de múltiples caracteres
     ID,
@@ -84,6 +92,52 @@ pub enum TokenType {
     InMultipleComment,
 }
 
+// Posición de un token dentro del código fuente.
+// Los bytes son desplazamientos en el texto; las líneas y columnas empiezan en 1
+// y `col_end` apunta a la columna siguiente al último carácter del lexema, que
+// está en `end_line`.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub line: usize,
+    pub end_line: usize,
+    pub col_start: usize,
+    pub col_end: usize,
+}
+
+impl Span {
+    // Span que va desde el inicio de `self` hasta el final de `end`
+    pub fn to(&self, end: &Span) -> Span {
+        Span {
+            start_byte: self.start_byte,
+            end_byte: end.end_byte,
+            line: self.line,
+            end_line: end.end_line,
+            col_start: self.col_start,
+            col_end: end.col_end,
+        }
+    }
+}
+
+// Estructura para representar un token reconocido por el escáner
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub kind: TokenType,
+    pub lexeme: String,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn new(kind: TokenType, lexeme: &str, span: Span) -> Self {
+        Token {
+            kind,
+            lexeme: lexeme.to_string(),
+            span,
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 // Parser /////////////////////////////////////////////////////////////////////////////////////////
@@ -92,16 +146,20 @@ pub enum TokenType {
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum NodeType {
     MainRoot,
+    Block,
     IntStatement,
     DoubleStatement,
+    Declarator,
     Statement,
     Expression,
     Term,
     Factor,
+    UnaryExpression,
     Assignment,
     IfStatement,
     ElseStatement,
     WhileStatement,
+    ForStatement,
     WriteStatement,
     ReadStatement,
     DoWhileStatement,
@@ -109,6 +167,7 @@ pub enum NodeType {
     SwitchStatement,
     CaseStatement,
     DefaultStatement,
+    BreakStatement,
     MainFunction,
     ReturnStatement,
     CinStatement,
@@ -118,6 +177,22 @@ pub enum NodeType {
     Error,
 }
 
+// Tipo calculado por el análisis semántico para cada expresión
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ExpType {
+    // Sentencias y nodos aún sin analizar
+    Void,
+    Integer,
+    Double,
+    Boolean,
+}
+
+impl Default for ExpType {
+    fn default() -> Self {
+        ExpType::Void
+    }
+}
+
 // Estructura para representar un nodo del árbol de sintaxis abstracta
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TreeNode {
@@ -125,6 +200,10 @@ pub struct TreeNode {
     pub token: Option<TokenType>,
     pub value: Option<String>,
     pub children: Vec<TreeNode>,
+    pub span: Span,
+    pub exp_type: ExpType,
+    // Valor conocido en tiempo de compilación, si la expresión es constante
+    pub constant: Option<Value>,
 }
 
 impl TreeNode {
@@ -134,6 +213,198 @@ impl TreeNode {
             token: None,
             value: None,
             children: Vec::new(),
+            span: Span::default(),
+            exp_type: ExpType::Void,
+            constant: None,
+        }
+    }
+
+    // Hoja con su token: identificadores, literales y operadores
+    pub fn leaf(node_type: NodeType, token: TokenType, value: String, span: Span) -> Self {
+        TreeNode {
+            token: Some(token),
+            value: Some(value),
+            span,
+            ..TreeNode::new(node_type)
+        }
+    }
+
+    // Sentencias de un brazo del switch: el último hijo del case y el único del default
+    pub fn arm_statements(&self) -> &TreeNode {
+        &self.children[self.children.len() - 1]
+    }
+
+    // do-while repite mientras la condición se cumple; repeat-until, hasta que se cumple
+    pub fn repeats_while_true(&self) -> bool {
+        self.node_type == NodeType::DoWhileStatement
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+// Valores ////////////////////////////////////////////////////////////////////////////////////////
+
+// Valor de una expresión, calculado al compilar (constantes) o al ejecutar
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+// Motivo por el que una operación no produce un valor
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EvalError {
+    DivisionByZero,
+    Overflow,
+    // Tipos de operandos que el operador no acepta (ya los reporta la verificación de tipos)
+    InvalidOperands,
+}
+
+impl Value {
+    // Valor de un literal: número entero, real o true/false
+    pub fn from_literal(token: &TokenType, lexeme: &str) -> Option<Value> {
+        match token {
+            TokenType::NumInt => lexeme.parse().ok().map(Value::Int),
+            TokenType::NumReal => lexeme.parse().ok().map(Value::Double),
+            TokenType::TRUE => Some(Value::Bool(true)),
+            TokenType::FALSE => Some(Value::Bool(false)),
+            _ => None,
+        }
+    }
+
+    // Valor numérico como double; los enteros se promueven
+    pub fn as_f64(self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(n as f64),
+            Value::Double(x) => Some(x),
+            Value::Bool(_) => None,
+        }
+    }
+
+    // Valor que queda guardado en una variable del tipo indicado: un int se
+    // promueve a double y cualquier otra mezcla no es válida
+    pub fn cast(self, exp_type: ExpType) -> Option<Value> {
+        match (exp_type, self) {
+            (ExpType::Integer, Value::Int(_))
+            | (ExpType::Double, Value::Double(_))
+            | (ExpType::Boolean, Value::Bool(_)) => Some(self),
+            (ExpType::Double, Value::Int(n)) => Some(Value::Double(n as f64)),
+            _ => None,
+        }
+    }
+
+    // Valor inicial de una variable declarada sin inicializador
+    pub fn default_for(exp_type: ExpType) -> Value {
+        match exp_type {
+            ExpType::Double => Value::Double(0.0),
+            ExpType::Boolean => Value::Bool(false),
+            _ => Value::Int(0),
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        matches!(self, Value::Int(0)) || self == Value::Double(0.0)
+    }
+
+    // Aplica un operador unario: -x, +x o !x
+    pub fn unary(operator: &TokenType, operand: Value) -> Result<Value, EvalError> {
+        match (operator, operand) {
+            (TokenType::MINUS, Value::Int(n)) => {
+                n.checked_neg().map(Value::Int).ok_or(EvalError::Overflow)
+            }
+            (TokenType::MINUS, Value::Double(x)) => Ok(Value::Double(-x)),
+            (TokenType::PLUS, Value::Int(_) | Value::Double(_)) => Ok(operand),
+            (TokenType::NOT, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            _ => Err(EvalError::InvalidOperands),
+        }
+    }
+
+    // Aplica un operador binario. Entre enteros la aritmética es entera (la división
+    // trunca); si algún operando es double, el otro se promueve.
+    pub fn binary(operator: &TokenType, left: Value, right: Value) -> Result<Value, EvalError> {
+        use TokenType::*;
+        match (left, right) {
+            (Value::Bool(a), Value::Bool(b)) => match operator {
+                AND => Ok(Value::Bool(a && b)),
+                OR => Ok(Value::Bool(a || b)),
+                EQ => Ok(Value::Bool(a == b)),
+                NEQ => Ok(Value::Bool(a != b)),
+                _ => Err(EvalError::InvalidOperands),
+            },
+            (Value::Int(a), Value::Int(b)) => {
+                let result = match operator {
+                    PLUS => a.checked_add(b),
+                    MINUS => a.checked_sub(b),
+                    TIMES => a.checked_mul(b),
+                    DIVIDE | MODULO if b == 0 => return Err(EvalError::DivisionByZero),
+                    DIVIDE => a.checked_div(b),
+                    MODULO => a.checked_rem(b),
+                    POWER => return int_power(a, b),
+                    _ => return compare(operator, a, b),
+                };
+                result.map(Value::Int).ok_or(EvalError::Overflow)
+            }
+            _ => {
+                let (a, b) = match (left.as_f64(), right.as_f64()) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return Err(EvalError::InvalidOperands),
+                };
+                match operator {
+                    PLUS => Ok(Value::Double(a + b)),
+                    MINUS => Ok(Value::Double(a - b)),
+                    TIMES => Ok(Value::Double(a * b)),
+                    DIVIDE if b == 0.0 => Err(EvalError::DivisionByZero),
+                    DIVIDE => Ok(Value::Double(a / b)),
+                    POWER => Ok(Value::Double(a.powf(b))),
+                    // El residuo solo está definido entre enteros
+                    MODULO => Err(EvalError::InvalidOperands),
+                    _ => compare(operator, a, b),
+                }
+            }
+        }
+    }
+}
+
+// Potencia entera. Con exponente negativo el resultado se trunca como en 1 / a^|b|
+fn int_power(base: i64, exponent: i64) -> Result<Value, EvalError> {
+    if exponent >= 0 {
+        let exponent = u32::try_from(exponent).map_err(|_| EvalError::Overflow)?;
+        return base
+            .checked_pow(exponent)
+            .map(Value::Int)
+            .ok_or(EvalError::Overflow);
+    }
+    match base {
+        0 => Err(EvalError::DivisionByZero),
+        1 => Ok(Value::Int(1)),
+        -1 => Ok(Value::Int(if exponent % 2 == 0 { 1 } else { -1 })),
+        _ => Ok(Value::Int(0)),
+    }
+}
+
+// Operadores relacionales entre números
+fn compare<T: PartialOrd>(operator: &TokenType, a: T, b: T) -> Result<Value, EvalError> {
+    let result = match operator {
+        TokenType::LT => a < b,
+        TokenType::LTE => a <= b,
+        TokenType::GT => a > b,
+        TokenType::GTE => a >= b,
+        TokenType::EQ => a == b,
+        TokenType::NEQ => a != b,
+        _ => return Err(EvalError::InvalidOperands),
+    };
+    Ok(Value::Bool(result))
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            // {:?} conserva el punto decimal: 2.0 y no 2
+            Value::Double(x) => write!(f, "{:?}", x),
+            Value::Bool(b) => write!(f, "{}", b),
         }
     }
 }
@@ -141,13 +412,157 @@ impl TreeNode {
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 // Errores /////////////////////////////////////////////////////////////////////////////////////////
-lazy_static! {
-    pub static ref ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Códigos de diagnóstico: E01xx léxicos, E02xx sintácticos, E03xx semánticos,
+// W03xx advertencias semánticas y E04xx errores de ejecución
+pub mod codes {
+    pub const UNEXPECTED_CHARACTER: &str = "E0101";
+    pub const MALFORMED_NUMBER: &str = "E0102";
+    pub const UNTERMINATED_COMMENT: &str = "E0103";
+
+    pub const EXPECTED_TOKEN: &str = "E0201";
+    pub const UNEXPECTED_TOKEN: &str = "E0202";
+    pub const EXPRESSION_WITHOUT_ASSIGNMENT: &str = "E0203";
+    pub const OUTSIDE_SWITCH: &str = "E0204";
+
+    pub const TYPE_MISMATCH: &str = "E0301";
+    pub const NON_BOOLEAN_CONDITION: &str = "E0302";
+    pub const NON_INTEGER_SWITCH: &str = "E0303";
+    pub const DUPLICATE_CASE_LABEL: &str = "E0304";
+    pub const INCOMPATIBLE_ASSIGNMENT: &str = "E0305";
+    pub const DOUBLE_MODULO: &str = "E0306";
+    pub const UNDECLARED_VARIABLE: &str = "E0307";
+    pub const REDECLARED_VARIABLE: &str = "E0308";
+    pub const DIVISION_BY_ZERO: &str = "E0309";
+    pub const BREAK_OUTSIDE_LOOP: &str = "E0310";
+    pub const CONSTANT_OVERFLOW: &str = "E0311";
+    pub const NUMBER_OUT_OF_RANGE: &str = "E0312";
+
+    pub const UNUSED_VARIABLE: &str = "W0301";
+
+    pub const RUNTIME_DIVISION_BY_ZERO: &str = "E0401";
+    pub const INTEGER_OVERFLOW: &str = "E0402";
+    pub const INVALID_INPUT: &str = "E0403";
+    pub const END_OF_INPUT: &str = "E0404";
+    pub const INVALID_OPERANDS: &str = "E0405";
+    pub const STEP_LIMIT_EXCEEDED: &str = "E0406";
+    pub const TIME_LIMIT_EXCEEDED: &str = "E0407";
+    pub const RUN_CANCELLED: &str = "E0408";
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+// Fragmento de código adicional que se señala junto al error principal
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// Diagnóstico producido por cualquiera de las fases del compilador
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    pub primary_span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+// Resultado de una fase que se detiene en el primer error. El error va en una caja
+// porque un Diagnostic es grande; se desempaca al guardarlo en los diagnósticos.
+pub type DiagnosticResult<T> = Result<T, Box<Diagnostic>>;
+
+impl Diagnostic {
+    pub fn new(code: &str, severity: Severity, message: String, primary_span: Span) -> Self {
+        Diagnostic {
+            code: code.to_string(),
+            severity,
+            message,
+            primary_span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn error(code: &str, message: String, primary_span: Span) -> Self {
+        Diagnostic::new(code, Severity::Error, message, primary_span)
+    }
+
+    pub fn warning(code: &str, message: String, primary_span: Span) -> Self {
+        Diagnostic::new(code, Severity::Warning, message, primary_span)
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help = Some(help);
+        self
+    }
+}
+
+// Colector de diagnósticos de una compilación. Cada petición crea el suyo y lo pasa por
+// referencia a las fases que lo necesitan, así dos análisis simultáneos no se mezclan.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+    // Código, span y mensaje de los diagnósticos ya registrados
+    #[serde(skip)]
+    seen: HashSet<(String, Span, String)>,
 }
 
-pub fn log_error(error: String) {
-    let mut errors = ERRORS.lock().unwrap();
-    if !errors.contains(&error) {
-        errors.push(error);
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics::default()
+    }
+
+    // Registra un diagnóstico; los repetidos se reportan una sola vez
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        let key = (
+            diagnostic.code.clone(),
+            diagnostic.primary_span.clone(),
+            diagnostic.message.clone(),
+        );
+        if self.seen.insert(key) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    pub fn error(&mut self, code: &str, message: String, span: Span) {
+        self.push(Diagnostic::error(code, message, span));
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.diagnostics
     }
 }
@@ -0,0 +1,668 @@
+use crate::globals::{
+    codes, Diagnostic, DiagnosticResult, EvalError, ExpType, NodeType, Span, TokenType, TreeNode,
+    Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Resultado de ejecutar o evaluar un nodo
+pub type ExecResult<T> = DiagnosticResult<T>;
+
+// Entrada y salida del programa en ejecución
+pub trait Console {
+    // Escribe el texto producido por cout o write
+    fn write(&mut self, text: &str);
+    // Lee la siguiente línea de entrada; None si ya no hay más
+    fn read_line(&mut self) -> Option<String>;
+}
+
+// Consola con toda la entrada disponible desde el inicio, para ejecuciones de una sola vez
+#[derive(Debug, Default)]
+pub struct BufferedConsole {
+    input: VecDeque<String>,
+    pub output: String,
+}
+
+impl BufferedConsole {
+    pub fn new(input: &str) -> Self {
+        BufferedConsole {
+            input: input.lines().map(|line| line.to_string()).collect(),
+            output: String::new(),
+        }
+    }
+}
+
+impl Console for BufferedConsole {
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        self.input.pop_front()
+    }
+}
+
+// Aviso de una consola interactiva hacia quien muestra el programa
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConsoleEvent {
+    // Texto escrito por cout o write
+    Output(String),
+    // Un cin o read espera la siguiente línea de entrada
+    InputRequested,
+}
+
+// Consola interactiva: avisa cada salida al momento y espera cada línea de
+// entrada en un canal, así el programa puede correr en otro hilo
+pub struct StreamConsole<F: FnMut(ConsoleEvent)> {
+    emit: F,
+    input: Receiver<String>,
+    // Copia de toda la salida, para el resultado final
+    pub output: String,
+}
+
+impl<F: FnMut(ConsoleEvent)> StreamConsole<F> {
+    pub fn new(input: Receiver<String>, emit: F) -> Self {
+        StreamConsole {
+            emit,
+            input,
+            output: String::new(),
+        }
+    }
+}
+
+impl<F: FnMut(ConsoleEvent)> Console for StreamConsole<F> {
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+        (self.emit)(ConsoleEvent::Output(text.to_string()));
+    }
+
+    // Si el emisor del canal se descarta la entrada se da por terminada
+    fn read_line(&mut self) -> Option<String> {
+        (self.emit)(ConsoleEvent::InputRequested);
+        self.input.recv().ok()
+    }
+}
+
+// Resultado de ejecutar un programa
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    pub output: String,
+    // Valor del return de main, o 0 si el programa termina sin él.
+    // None si la ejecución se detuvo por un error.
+    pub exit_value: Option<Value>,
+    pub errors: Vec<Diagnostic>,
+}
+
+impl RunResult {
+    // Resultado de una ejecución terminada, con toda su salida
+    pub fn finished(output: String, result: ExecResult<Value>) -> Self {
+        let (exit_value, errors) = match result {
+            Ok(value) => (Some(value), Vec::new()),
+            Err(err) => (None, vec![*err]),
+        };
+        RunResult {
+            output,
+            exit_value,
+            errors,
+        }
+    }
+
+    // Programa que no llega a ejecutarse por errores de compilación
+    pub fn not_compiled(errors: Vec<Diagnostic>) -> Self {
+        RunResult {
+            output: String::new(),
+            exit_value: None,
+            errors,
+        }
+    }
+}
+
+// Límites de una ejecución, para que un ciclo infinito no congele el IDE
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    // Sentencias que puede ejecutar el programa
+    pub max_steps: u64,
+    // Tiempo máximo de ejecución en milisegundos, sin contar la espera de entrada
+    pub timeout_ms: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: 10_000_000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+const LOOP_HELP: &str = "revisa que la condición de los ciclos llegue a ser falsa";
+
+// Variable viva durante la ejecución
+pub struct Variable {
+    pub name: String,
+    pub exp_type: ExpType,
+    pub value: Value,
+    // Declarador que la creó
+    pub declared_at: Span,
+}
+
+// Observa la ejecución antes de cada sentencia; el depurador lo usa para detenerla.
+// `depth` cuenta las sentencias que encierran a la actual y `frames` son los
+// ámbitos abiertos, del global al más interno.
+pub trait Observer {
+    fn before_statement(&mut self, statement: &TreeNode, depth: usize, frames: &[Vec<Variable>]);
+
+    // Antes de cada evaluación de la condición de un ciclo; `depth` es la del ciclo
+    fn before_condition(&mut self, condition: &TreeNode, depth: usize, frames: &[Vec<Variable>]) {
+        let _ = (condition, depth, frames);
+    }
+}
+
+// Cómo termina la ejecución de una sentencia
+enum Flow {
+    Normal,
+    Break,
+    Return(Value),
+}
+
+// Intérprete que recorre el árbol de sintaxis abstracta ya analizado
+pub struct Interpreter<'a, C: Console> {
+    console: &'a mut C,
+    // Un marco por cada ámbito abierto; el último es el más interno
+    frames: Vec<Vec<Variable>>,
+    // Palabras de la última línea leída que aún no consume ningún cin
+    pending: VecDeque<String>,
+    limits: Limits,
+    // Se activa desde otro hilo para detener el programa
+    cancel: Arc<AtomicBool>,
+    steps: u64,
+    started: Instant,
+    // Tiempo esperando entrada o detenido en el depurador, que no cuenta para
+    // el límite de tiempo
+    waiting: Duration,
+    observer: Option<&'a mut dyn Observer>,
+    // Sentencias en ejecución que encierran a la actual
+    depth: usize,
+}
+
+impl<'a, C: Console> Interpreter<'a, C> {
+    pub fn new(console: &'a mut C) -> Self {
+        Interpreter {
+            console,
+            frames: Vec::new(),
+            pending: VecDeque::new(),
+            limits: Limits::default(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            steps: 0,
+            started: Instant::now(),
+            waiting: Duration::ZERO,
+            observer: None,
+            depth: 0,
+        }
+    }
+
+    pub fn with_observer(mut self, observer: &'a mut dyn Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // Bandera compartida con quien puede cancelar la ejecución
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    // Cuenta un paso más y detiene el programa si se canceló o se pasó de algún
+    // límite; el error señala la sentencia que se estaba ejecutando
+    fn check_limits(&mut self, t: &TreeNode) -> ExecResult<()> {
+        self.check_cancelled(t)?;
+        self.steps += 1;
+        if self.steps > self.limits.max_steps {
+            return Err(Box::new(
+                Diagnostic::error(
+                    codes::STEP_LIMIT_EXCEEDED,
+                    format!(
+                        "El programa superó el límite de {} pasos en la línea {}",
+                        self.limits.max_steps, t.span.line
+                    ),
+                    t.span.clone(),
+                )
+                .with_help(LOOP_HELP.to_string()),
+            ));
+        }
+        let elapsed = self.started.elapsed().saturating_sub(self.waiting);
+        if elapsed > Duration::from_millis(self.limits.timeout_ms) {
+            return Err(Box::new(
+                Diagnostic::error(
+                    codes::TIME_LIMIT_EXCEEDED,
+                    format!(
+                        "El programa superó el límite de {} ms en la línea {}",
+                        self.limits.timeout_ms, t.span.line
+                    ),
+                    t.span.clone(),
+                )
+                .with_help(LOOP_HELP.to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_cancelled(&self, t: &TreeNode) -> ExecResult<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(Box::new(Diagnostic::error(
+                codes::RUN_CANCELLED,
+                format!("Ejecución cancelada en la línea {}", t.span.line),
+                t.span.clone(),
+            )));
+        }
+        Ok(())
+    }
+
+    // Ejecuta el programa completo y devuelve el valor con el que termina
+    pub fn run(&mut self, syntax_tree: &TreeNode) -> ExecResult<Value> {
+        self.started = Instant::now();
+        match self.exec_block(syntax_tree)? {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal | Flow::Break => Ok(Value::Int(0)),
+        }
+    }
+
+    // Ejecuta las sentencias de un bloque en un ámbito nuevo
+    fn exec_block(&mut self, t: &TreeNode) -> ExecResult<Flow> {
+        self.frames.push(Vec::new());
+        let mut flow = Ok(Flow::Normal);
+        for statement in &t.children {
+            flow = self.exec(statement);
+            if !matches!(flow, Ok(Flow::Normal)) {
+                break;
+            }
+        }
+        self.frames.pop();
+        flow
+    }
+
+    // Ejecuta una sentencia; los bloques y main solo agrupan, así que el
+    // observador no los ve
+    fn exec(&mut self, t: &TreeNode) -> ExecResult<Flow> {
+        self.check_limits(t)?;
+        if matches!(
+            t.node_type,
+            NodeType::MainRoot | NodeType::Block | NodeType::MainFunction
+        ) {
+            return self.exec_statement(t);
+        }
+        if let Some(observer) = self.observer.as_mut() {
+            let paused_since = Instant::now();
+            observer.before_statement(t, self.depth, &self.frames);
+            self.waiting += paused_since.elapsed();
+            // Pudo cancelarse mientras estaba detenido
+            self.check_cancelled(t)?;
+        }
+        self.depth += 1;
+        let flow = self.exec_statement(t);
+        self.depth -= 1;
+        flow
+    }
+
+    fn exec_statement(&mut self, t: &TreeNode) -> ExecResult<Flow> {
+        match t.node_type {
+            NodeType::MainRoot | NodeType::Block => return self.exec_block(t),
+            NodeType::MainFunction => return self.exec_block(&t.children[0]),
+            NodeType::IntStatement | NodeType::DoubleStatement => {
+                let exp_type = if t.node_type == NodeType::DoubleStatement {
+                    ExpType::Double
+                } else {
+                    ExpType::Integer
+                };
+                for declarator in &t.children {
+                    self.declare(declarator, exp_type)?;
+                }
+            }
+            NodeType::Assignment => {
+                let value = self.eval(&t.children[1])?;
+                self.store(&t.children[0], value)?;
+            }
+            NodeType::Increment | NodeType::Decrement => {
+                let step = if t.node_type == NodeType::Increment {
+                    TokenType::PLUS
+                } else {
+                    TokenType::MINUS
+                };
+                let target = &t.children[0];
+                let value = self.load(target)?;
+                let value = self.apply_binary(t, &step, value, Value::Int(1))?;
+                self.store(target, value)?;
+            }
+            NodeType::IfStatement => {
+                if self.eval_condition(&t.children[0])? {
+                    return self.exec(&t.children[1]);
+                } else if let Some(else_node) = t.children.get(2) {
+                    return self.exec(&else_node.children[0]);
+                }
+            }
+            NodeType::WhileStatement => {
+                while self.eval_loop_condition(&t.children[0])? {
+                    match self.exec(&t.children[1])? {
+                        Flow::Normal => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            NodeType::DoWhileStatement | NodeType::RepeatUntilStatement => {
+                let repeat_while = t.repeats_while_true();
+                loop {
+                    match self.exec(&t.children[0])? {
+                        Flow::Normal => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                    if self.eval_loop_condition(&t.children[1])? != repeat_while {
+                        break;
+                    }
+                }
+            }
+            NodeType::ForStatement => {
+                // Lo declarado en la inicialización vive en un ámbito propio del ciclo
+                self.frames.push(Vec::new());
+                let flow = self.exec_for(t);
+                self.frames.pop();
+                return flow;
+            }
+            NodeType::SwitchStatement => return self.exec_switch(t),
+            NodeType::BreakStatement => return Ok(Flow::Break),
+            NodeType::ReturnStatement => {
+                let value = self.eval(&t.children[0])?;
+                return Ok(Flow::Return(value));
+            }
+            NodeType::CinStatement | NodeType::ReadStatement => {
+                let target = &t.children[0];
+                let value = self.read_value(target)?;
+                self.store(target, value)?;
+            }
+            NodeType::CoutStatement | NodeType::WriteStatement => {
+                let value = self.eval(&t.children[0])?;
+                self.console.write(&format!("{}\n", value));
+            }
+            _ => {}
+        }
+        Ok(Flow::Normal)
+    }
+
+    // for (inicialización; condición; actualización) cuerpo
+    fn exec_for(&mut self, t: &TreeNode) -> ExecResult<Flow> {
+        self.exec(&t.children[0])?;
+        while self.eval_loop_condition(&t.children[1])? {
+            match self.exec(&t.children[3])? {
+                Flow::Normal => {}
+                Flow::Break => break,
+                flow => return Ok(flow),
+            }
+            self.exec(&t.children[2])?;
+        }
+        Ok(Flow::Normal)
+    }
+
+    // Ejecuta desde el primer case que coincide con el selector (o desde el default)
+    // hasta el final del switch o hasta un break
+    fn exec_switch(&mut self, t: &TreeNode) -> ExecResult<Flow> {
+        let selector = self.eval(&t.children[0])?;
+        let arms = &t.children[1..];
+        let start = arms
+            .iter()
+            .position(|arm| {
+                arm.node_type == NodeType::CaseStatement
+                    && arm.children[0].constant == Some(selector)
+            })
+            .or_else(|| {
+                arms.iter()
+                    .position(|arm| arm.node_type == NodeType::DefaultStatement)
+            });
+        let start = match start {
+            Some(start) => start,
+            None => return Ok(Flow::Normal),
+        };
+        for arm in &arms[start..] {
+            match self.exec(arm.arm_statements())? {
+                Flow::Normal => {}
+                Flow::Break => break,
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    // Declara una variable en el ámbito actual. Se declara antes de evaluar el
+    // inicializador, igual que en el análisis semántico.
+    fn declare(&mut self, t: &TreeNode, exp_type: ExpType) -> ExecResult<()> {
+        let name = t.value.clone().unwrap_or_default();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push(Variable {
+                name,
+                exp_type,
+                value: Value::default_for(exp_type),
+                declared_at: t.span.clone(),
+            });
+        }
+        if let Some(initializer) = t.children.first() {
+            let value = self.eval(initializer)?;
+            self.store(t, value)?;
+        }
+        Ok(())
+    }
+
+    // Variable visible con ese nombre, del ámbito más interno al global
+    fn variable(&mut self, t: &TreeNode) -> ExecResult<&mut Variable> {
+        let name = t.value.as_deref().unwrap_or_default();
+        self.frames
+            .iter_mut()
+            .rev()
+            .flat_map(|frame| frame.iter_mut().rev())
+            .find(|variable| variable.name == name)
+            .ok_or_else(|| {
+                Box::new(Diagnostic::error(
+                    codes::UNDECLARED_VARIABLE,
+                    format!("La variable '{}' no está declarada", name),
+                    t.span.clone(),
+                ))
+            })
+    }
+
+    fn load(&mut self, t: &TreeNode) -> ExecResult<Value> {
+        Ok(self.variable(t)?.value)
+    }
+
+    // Guarda un valor en la variable; un int se promueve si la variable es double
+    fn store(&mut self, t: &TreeNode, value: Value) -> ExecResult<()> {
+        let variable = self.variable(t)?;
+        match value.cast(variable.exp_type) {
+            Some(value) => {
+                variable.value = value;
+                Ok(())
+            }
+            None => Err(Box::new(Diagnostic::error(
+                codes::INCOMPATIBLE_ASSIGNMENT,
+                format!(
+                    "No se puede guardar el valor {} en la variable '{}'",
+                    value, variable.name
+                ),
+                t.span.clone(),
+            ))),
+        }
+    }
+
+    // Lee la siguiente palabra de la entrada y la convierte al tipo de la variable
+    fn read_value(&mut self, target: &TreeNode) -> ExecResult<Value> {
+        let exp_type = self.variable(target)?.exp_type;
+        let name = target.value.clone().unwrap_or_default();
+        while self.pending.is_empty() {
+            let waiting_since = Instant::now();
+            let line = self.console.read_line();
+            self.waiting += waiting_since.elapsed();
+            // Cancelar mientras espera entrada también cierra la entrada
+            self.check_cancelled(target)?;
+            let line = match line {
+                Some(line) => line,
+                None => {
+                    return Err(Box::new(Diagnostic::error(
+                        codes::END_OF_INPUT,
+                        format!(
+                            "Se esperaba un valor para '{}' pero la entrada terminó",
+                            name
+                        ),
+                        target.span.clone(),
+                    )))
+                }
+            };
+            self.pending
+                .extend(line.split_whitespace().map(|word| word.to_string()));
+        }
+        let word = self.pending.pop_front().unwrap_or_default();
+        let value = match exp_type {
+            ExpType::Double => word.parse().ok().map(Value::Double),
+            _ => word.parse().ok().map(Value::Int),
+        };
+        value.ok_or_else(|| {
+            let type_name = if exp_type == ExpType::Double {
+                "double"
+            } else {
+                "int"
+            };
+            Box::new(Diagnostic::error(
+                codes::INVALID_INPUT,
+                format!(
+                    "La entrada '{}' no es un valor {} válido para '{}'",
+                    word, type_name, name
+                ),
+                target.span.clone(),
+            ))
+        })
+    }
+
+    // Condición de un ciclo, evaluada en cada vuelta; el observador la ve antes.
+    // Se evalúa dentro del ciclo, así que su profundidad es una menos que la actual.
+    fn eval_loop_condition(&mut self, t: &TreeNode) -> ExecResult<bool> {
+        if let Some(observer) = self.observer.as_mut() {
+            let paused_since = Instant::now();
+            observer.before_condition(t, self.depth - 1, &self.frames);
+            self.waiting += paused_since.elapsed();
+            self.check_cancelled(t)?;
+        }
+        self.eval_condition(t)
+    }
+
+    fn eval_condition(&mut self, t: &TreeNode) -> ExecResult<bool> {
+        match self.eval(t)? {
+            Value::Bool(b) => Ok(b),
+            Value::Int(n) => Ok(n != 0),
+            value => Err(Box::new(Diagnostic::error(
+                codes::INVALID_OPERANDS,
+                format!("La condición vale {} y no es booleana", value),
+                t.span.clone(),
+            ))),
+        }
+    }
+
+    fn eval(&mut self, t: &TreeNode) -> ExecResult<Value> {
+        match t.node_type {
+            NodeType::Factor => match &t.token {
+                Some(TokenType::ID) => self.load(t),
+                // ( expresión )
+                None => self.eval(&t.children[0]),
+                Some(token) => {
+                    let lexeme = t.value.clone().unwrap_or_default();
+                    Value::from_literal(token, &lexeme).ok_or_else(|| {
+                        Box::new(Diagnostic::error(
+                            codes::INVALID_OPERANDS,
+                            format!("'{}' no es un valor", lexeme),
+                            t.span.clone(),
+                        ))
+                    })
+                }
+            },
+            NodeType::UnaryExpression => {
+                // -9223372036854775808 solo tiene valor ya plegado: sin el signo no cabe en un int
+                if let Some(value) = t.constant {
+                    return Ok(value);
+                }
+                let operand = self.eval(&t.children[1])?;
+                let operator = t.children[0].token.clone().unwrap_or(TokenType::ERROR);
+                Value::unary(&operator, operand).map_err(|err| self.eval_error(t, err))
+            }
+            NodeType::Expression | NodeType::Term => {
+                let operator = t.children[1].token.clone().unwrap_or(TokenType::ERROR);
+                let left = self.eval(&t.children[0])?;
+                // && y || se evalúan en cortocircuito
+                match (&operator, left) {
+                    (TokenType::AND, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (TokenType::OR, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let right = self.eval(&t.children[2])?;
+                self.apply_binary(t, &operator, left, right)
+            }
+            _ => Err(Box::new(Diagnostic::error(
+                codes::INVALID_OPERANDS,
+                "El nodo no es una expresión".to_string(),
+                t.span.clone(),
+            ))),
+        }
+    }
+
+    fn apply_binary(
+        &self,
+        t: &TreeNode,
+        operator: &TokenType,
+        left: Value,
+        right: Value,
+    ) -> ExecResult<Value> {
+        Value::binary(operator, left, right).map_err(|err| self.eval_error(t, err))
+    }
+
+    // Error de ejecución al aplicar un operador en el nodo indicado
+    fn eval_error(&self, t: &TreeNode, err: EvalError) -> Box<Diagnostic> {
+        Box::new(match err {
+            EvalError::DivisionByZero => {
+                let diagnostic = Diagnostic::error(
+                    codes::RUNTIME_DIVISION_BY_ZERO,
+                    "División entre cero".to_string(),
+                    t.span.clone(),
+                );
+                match t.children.get(2) {
+                    Some(divisor) => diagnostic
+                        .with_label(divisor.span.clone(), "este divisor vale 0".to_string()),
+                    None => diagnostic,
+                }
+            }
+            EvalError::Overflow => Diagnostic::error(
+                codes::INTEGER_OVERFLOW,
+                "El resultado no cabe en un int".to_string(),
+                t.span.clone(),
+            ),
+            EvalError::InvalidOperands => Diagnostic::error(
+                codes::INVALID_OPERANDS,
+                "Operandos inválidos para el operador".to_string(),
+                t.span.clone(),
+            ),
+        })
+    }
+}
+
+// Ejecuta un programa ya analizado con la entrada y los límites indicados
+pub fn run(syntax_tree: &TreeNode, input: &str, limits: Limits) -> RunResult {
+    let mut console = BufferedConsole::new(input);
+    let result = Interpreter::new(&mut console)
+        .with_limits(limits)
+        .run(syntax_tree);
+    RunResult::finished(console.output, result)
+}
@@ -0,0 +1,515 @@
+use crate::globals::{ExpType, NodeType, TokenType, TreeNode, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+// Código intermedio ///////////////////////////////////////////////////////////////////////////////
+
+// Operación de una cuádrupla. En el JSON se guarda con el mismo símbolo que en
+// el texto (`*`, `if_false`, ...), salvo el menos unario, que es `minus` para no
+// confundirlo con la resta.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Op {
+    #[serde(rename = "=")]
+    Assign,
+    #[serde(rename = "+")]
+    Add,
+    #[serde(rename = "-")]
+    Sub,
+    #[serde(rename = "*")]
+    Mul,
+    // La división entre enteros trunca; se distingue de la división entre doubles
+    #[serde(rename = "/i")]
+    DivInt,
+    #[serde(rename = "/d")]
+    DivDouble,
+    #[serde(rename = "%")]
+    Mod,
+    #[serde(rename = "^")]
+    Pow,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Lte,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Gte,
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Neq,
+    #[serde(rename = "minus")]
+    Neg,
+    #[serde(rename = "!")]
+    Not,
+    #[serde(rename = "label")]
+    Label,
+    #[serde(rename = "goto")]
+    Goto,
+    #[serde(rename = "if_true")]
+    IfTrue,
+    #[serde(rename = "if_false")]
+    IfFalse,
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "write")]
+    Write,
+    #[serde(rename = "return")]
+    Return,
+    #[serde(rename = "halt")]
+    Halt,
+}
+
+impl Op {
+    // Operación de un operador binario del árbol sobre operandos del tipo indicado;
+    // && y || no tienen, se traducen con saltos
+    fn binary(token: &TokenType, exp_type: ExpType) -> Option<Op> {
+        Some(match token {
+            TokenType::PLUS => Op::Add,
+            TokenType::MINUS => Op::Sub,
+            TokenType::TIMES => Op::Mul,
+            TokenType::DIVIDE if exp_type == ExpType::Double => Op::DivDouble,
+            TokenType::DIVIDE => Op::DivInt,
+            TokenType::MODULO => Op::Mod,
+            TokenType::POWER => Op::Pow,
+            TokenType::LT => Op::Lt,
+            TokenType::LTE => Op::Lte,
+            TokenType::GT => Op::Gt,
+            TokenType::GTE => Op::Gte,
+            TokenType::EQ => Op::Eq,
+            TokenType::NEQ => Op::Neq,
+            _ => return None,
+        })
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Op::Assign => "=",
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::DivInt => "/i",
+            Op::DivDouble => "/d",
+            Op::Mod => "%",
+            Op::Pow => "^",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Eq => "==",
+            Op::Neq => "!=",
+            Op::Neg => "-",
+            Op::Not => "!",
+            Op::Label => "label",
+            Op::Goto => "goto",
+            Op::IfTrue => "if_true",
+            Op::IfFalse => "if_false",
+            Op::Read => "read",
+            Op::Write => "write",
+            Op::Return => "return",
+            Op::Halt => "halt",
+        }
+    }
+}
+
+// Cuádrupla (op, arg1, arg2, result). Los operandos son variables, constantes,
+// temporales (t1, t2, ...) o etiquetas (L1, L2, ...).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Quadruple {
+    pub op: Op,
+    pub arg1: Option<String>,
+    pub arg2: Option<String>,
+    pub result: Option<String>,
+}
+
+impl Quadruple {
+    fn new(op: Op, arg1: Option<String>, arg2: Option<String>, result: Option<String>) -> Self {
+        Quadruple {
+            op,
+            arg1,
+            arg2,
+            result,
+        }
+    }
+}
+
+// Forma textual de código de tres direcciones: `t1 = a * b`, `if_false t2 goto L3`
+impl fmt::Display for Quadruple {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arg1 = self.arg1.as_deref().unwrap_or_default();
+        let arg2 = self.arg2.as_deref().unwrap_or_default();
+        let result = self.result.as_deref().unwrap_or_default();
+        match self.op {
+            Op::Assign => write!(f, "{} = {}", result, arg1),
+            Op::Neg | Op::Not => write!(f, "{} = {}{}", result, self.op.symbol(), arg1),
+            Op::Label => write!(f, "{}:", result),
+            Op::Goto => write!(f, "goto {}", result),
+            Op::IfTrue | Op::IfFalse => {
+                write!(f, "{} {} goto {}", self.op.symbol(), arg1, result)
+            }
+            Op::Read => write!(f, "read {}", result),
+            Op::Write | Op::Return => write!(f, "{} {}", self.op.symbol(), arg1),
+            Op::Halt => write!(f, "halt"),
+            _ => write!(f, "{} = {} {} {}", result, arg1, self.op.symbol(), arg2),
+        }
+    }
+}
+
+// Código intermedio de un programa completo
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct IntermediateCode {
+    pub quadruples: Vec<Quadruple>,
+}
+
+impl IntermediateCode {
+    // Una instrucción por renglón; las que no son etiquetas van sangradas
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for quadruple in &self.quadruples {
+            if quadruple.op != Op::Label {
+                text.push_str("    ");
+            }
+            text.push_str(&quadruple.to_string());
+            text.push('\n');
+        }
+        text
+    }
+
+    // Exporta la tabla de cuádruplas como un arreglo JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.quadruples)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+// Generación //////////////////////////////////////////////////////////////////////////////////////
+
+struct CodeGenerator {
+    code: Vec<Quadruple>,
+    temporaries: usize,
+    labels: usize,
+    // Nombre en el código de cada variable visible, por ámbito
+    scopes: Vec<HashMap<String, String>>,
+    // Veces que se ha declarado cada nombre; una variable que oculta a otra
+    // se renombra como `x.2`, `x.3`, ...
+    declared: HashMap<String, usize>,
+    // Etiqueta a la que salta un break, del ciclo o switch más interno
+    break_labels: Vec<String>,
+}
+
+impl CodeGenerator {
+    fn new() -> Self {
+        CodeGenerator {
+            code: Vec::new(),
+            temporaries: 0,
+            labels: 0,
+            scopes: Vec::new(),
+            declared: HashMap::new(),
+            break_labels: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self, op: Op, arg1: Option<String>, arg2: Option<String>, result: Option<String>) {
+        self.code.push(Quadruple::new(op, arg1, arg2, result));
+    }
+
+    fn new_temporary(&mut self) -> String {
+        self.temporaries += 1;
+        format!("t{}", self.temporaries)
+    }
+
+    fn new_label(&mut self) -> String {
+        self.labels += 1;
+        format!("L{}", self.labels)
+    }
+
+    fn label(&mut self, label: &str) {
+        self.emit(Op::Label, None, None, Some(label.to_string()));
+    }
+
+    fn goto(&mut self, label: &str) {
+        self.emit(Op::Goto, None, None, Some(label.to_string()));
+    }
+
+    fn jump_if(&mut self, op: Op, condition: String, label: &str) {
+        self.emit(op, Some(condition), None, Some(label.to_string()));
+    }
+
+    fn assign(&mut self, target: String, value: String) {
+        self.emit(Op::Assign, Some(value), None, Some(target));
+    }
+
+    fn declare(&mut self, name: &str) -> String {
+        let count = self.declared.entry(name.to_string()).or_insert(0);
+        *count += 1;
+        let unique = if *count == 1 {
+            name.to_string()
+        } else {
+            format!("{}.{}", name, count)
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), unique.clone());
+        }
+        unique
+    }
+
+    // Nombre en el código de la variable visible con el nombre del nodo
+    fn variable(&self, t: &TreeNode) -> String {
+        let name = t.value.clone().unwrap_or_default();
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name).cloned())
+            .unwrap_or(name)
+    }
+
+    fn gen_block(&mut self, t: &TreeNode) {
+        self.scopes.push(HashMap::new());
+        for statement in &t.children {
+            self.gen_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    // Cuerpo de un ciclo: un break dentro salta a `exit`
+    fn gen_loop_body(&mut self, body: &TreeNode, exit: &str) {
+        self.break_labels.push(exit.to_string());
+        self.gen_statement(body);
+        self.break_labels.pop();
+    }
+
+    fn gen_statement(&mut self, t: &TreeNode) {
+        match t.node_type {
+            NodeType::MainRoot | NodeType::Block => self.gen_block(t),
+            NodeType::MainFunction => self.gen_block(&t.children[0]),
+            NodeType::IntStatement | NodeType::DoubleStatement => {
+                for declarator in &t.children {
+                    self.gen_declarator(declarator);
+                }
+            }
+            NodeType::Assignment => {
+                let target = &t.children[0];
+                let value = self.gen_value(&t.children[1], target.exp_type);
+                let name = self.variable(target);
+                self.assign(name, value);
+            }
+            NodeType::Increment | NodeType::Decrement => {
+                let op = if t.node_type == NodeType::Increment {
+                    Op::Add
+                } else {
+                    Op::Sub
+                };
+                let name = self.variable(&t.children[0]);
+                self.emit(op, Some(name.clone()), Some("1".to_string()), Some(name));
+            }
+            NodeType::IfStatement => {
+                let condition = self.gen_expression(&t.children[0]);
+                let end = self.new_label();
+                match t.children.get(2) {
+                    Some(else_node) => {
+                        let otherwise = self.new_label();
+                        self.jump_if(Op::IfFalse, condition, &otherwise);
+                        self.gen_statement(&t.children[1]);
+                        self.goto(&end);
+                        self.label(&otherwise);
+                        self.gen_block(&else_node.children[0]);
+                    }
+                    None => {
+                        self.jump_if(Op::IfFalse, condition, &end);
+                        self.gen_statement(&t.children[1]);
+                    }
+                }
+                self.label(&end);
+            }
+            NodeType::WhileStatement => {
+                let start = self.new_label();
+                let end = self.new_label();
+                self.label(&start);
+                let condition = self.gen_expression(&t.children[0]);
+                self.jump_if(Op::IfFalse, condition, &end);
+                self.gen_loop_body(&t.children[1], &end);
+                self.goto(&start);
+                self.label(&end);
+            }
+            NodeType::DoWhileStatement | NodeType::RepeatUntilStatement => {
+                let repeat = if t.repeats_while_true() {
+                    Op::IfTrue
+                } else {
+                    Op::IfFalse
+                };
+                let start = self.new_label();
+                let end = self.new_label();
+                self.label(&start);
+                self.gen_loop_body(&t.children[0], &end);
+                let condition = self.gen_expression(&t.children[1]);
+                self.jump_if(repeat, condition, &start);
+                self.label(&end);
+            }
+            NodeType::ForStatement => {
+                let start = self.new_label();
+                let end = self.new_label();
+                // Lo declarado en la inicialización solo es visible dentro del ciclo
+                self.scopes.push(HashMap::new());
+                self.gen_statement(&t.children[0]);
+                self.label(&start);
+                let condition = self.gen_expression(&t.children[1]);
+                self.jump_if(Op::IfFalse, condition, &end);
+                self.gen_loop_body(&t.children[3], &end);
+                self.gen_statement(&t.children[2]);
+                self.goto(&start);
+                self.label(&end);
+                self.scopes.pop();
+            }
+            NodeType::SwitchStatement => self.gen_switch(t),
+            NodeType::BreakStatement => {
+                if let Some(exit) = self.break_labels.last().cloned() {
+                    self.goto(&exit);
+                }
+            }
+            NodeType::ReturnStatement => {
+                let value = self.gen_expression(&t.children[0]);
+                self.emit(Op::Return, Some(value), None, None);
+            }
+            NodeType::CinStatement | NodeType::ReadStatement => {
+                let name = self.variable(&t.children[0]);
+                self.emit(Op::Read, None, None, Some(name));
+            }
+            NodeType::CoutStatement | NodeType::WriteStatement => {
+                let value = self.gen_expression(&t.children[0]);
+                self.emit(Op::Write, Some(value), None, None);
+            }
+            _ => {}
+        }
+    }
+
+    // Igual que en el intérprete, la variable se declara antes de evaluar el
+    // inicializador, y sin inicializador empieza en 0
+    fn gen_declarator(&mut self, t: &TreeNode) {
+        let name = t.value.clone().unwrap_or_default();
+        let unique = self.declare(&name);
+        let value = match t.children.first() {
+            Some(initializer) => self.gen_value(initializer, t.exp_type),
+            None => Value::default_for(t.exp_type).to_string(),
+        };
+        self.assign(unique, value);
+    }
+
+    // Compara el selector con cada case en orden y salta al primero que coincide
+    // (o al default); los brazos quedan seguidos, así que sin break se sigue al siguiente
+    fn gen_switch(&mut self, t: &TreeNode) {
+        let selector = self.gen_expression(&t.children[0]);
+        let end = self.new_label();
+        let arms = &t.children[1..];
+        let labels: Vec<String> = arms.iter().map(|_| self.new_label()).collect();
+        for (arm, label) in arms.iter().zip(&labels) {
+            if arm.node_type == NodeType::CaseStatement {
+                let matches = self.new_temporary();
+                let case_value = arm.children[0]
+                    .constant
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                self.emit(
+                    Op::Eq,
+                    Some(selector.clone()),
+                    Some(case_value),
+                    Some(matches.clone()),
+                );
+                self.jump_if(Op::IfTrue, matches, label);
+            }
+        }
+        let default = arms
+            .iter()
+            .position(|arm| arm.node_type == NodeType::DefaultStatement);
+        match default {
+            Some(index) => self.goto(&labels[index]),
+            None => self.goto(&end),
+        }
+        self.break_labels.push(end.clone());
+        for (arm, label) in arms.iter().zip(&labels) {
+            self.label(label);
+            self.gen_statement(arm.arm_statements());
+        }
+        self.break_labels.pop();
+        self.label(&end);
+    }
+
+    // Valor que se guarda en una variable del tipo indicado; una constante int
+    // se escribe ya convertida si la variable es double
+    fn gen_value(&mut self, t: &TreeNode, target: ExpType) -> String {
+        match t.constant.and_then(|value| value.cast(target)) {
+            Some(value) => value.to_string(),
+            None => self.gen_expression(t),
+        }
+    }
+
+    // Genera el código de una expresión y devuelve el operando con su resultado
+    fn gen_expression(&mut self, t: &TreeNode) -> String {
+        if let Some(value) = t.constant {
+            return value.to_string();
+        }
+        match t.node_type {
+            NodeType::Factor => match &t.token {
+                Some(TokenType::ID) => self.variable(t),
+                // ( expresión )
+                None => self.gen_expression(&t.children[0]),
+                Some(_) => t.value.clone().unwrap_or_default(),
+            },
+            NodeType::UnaryExpression => {
+                let operand = self.gen_expression(&t.children[1]);
+                let op = match t.children[0].token {
+                    Some(TokenType::MINUS) => Op::Neg,
+                    Some(TokenType::NOT) => Op::Not,
+                    // El signo + deja el valor igual
+                    _ => return operand,
+                };
+                let result = self.new_temporary();
+                self.emit(op, Some(operand), None, Some(result.clone()));
+                result
+            }
+            NodeType::Expression | NodeType::Term => {
+                let operator = t.children[1].token.clone().unwrap_or(TokenType::ERROR);
+                match Op::binary(&operator, t.exp_type) {
+                    Some(op) => {
+                        let left = self.gen_expression(&t.children[0]);
+                        let right = self.gen_expression(&t.children[2]);
+                        let result = self.new_temporary();
+                        self.emit(op, Some(left), Some(right), Some(result.clone()));
+                        result
+                    }
+                    None => self.gen_short_circuit(t, &operator),
+                }
+            }
+            _ => String::new(),
+        }
+    }
+
+    // a && b y a || b: el lado derecho solo se evalúa si hace falta
+    fn gen_short_circuit(&mut self, t: &TreeNode, operator: &TokenType) -> String {
+        let skip = if *operator == TokenType::OR {
+            Op::IfTrue
+        } else {
+            Op::IfFalse
+        };
+        let result = self.new_temporary();
+        let end = self.new_label();
+        let left = self.gen_expression(&t.children[0]);
+        self.assign(result.clone(), left);
+        self.jump_if(skip, result.clone(), &end);
+        let right = self.gen_expression(&t.children[2]);
+        self.assign(result.clone(), right);
+        self.label(&end);
+        result
+    }
+}
+
+// Traduce un programa ya analizado a código de tres direcciones
+pub fn generate(syntax_tree: &TreeNode) -> IntermediateCode {
+    let mut generator = CodeGenerator::new();
+    generator.gen_statement(syntax_tree);
+    generator.emit(Op::Halt, None, None, None);
+    IntermediateCode {
+        quadruples: generator.code,
+    }
+}
@@ -1,20 +1,86 @@
-use crate::globals::{StateType, TokenType};
+use crate::globals::{codes, Diagnostic, Diagnostics, Span, StateType, Token, TokenType};
+use std::str::Chars;
 
-// Función para obtener el siguiente carácter no en blanco de la línea actual
-fn get_next_char(line: &str, linepos: &mut usize, bufsize: usize) -> char {
-    if *linepos >= bufsize {
-        '\0' // Devuelve un carácter nulo al final de la línea
-    } else {
-        let c = line.chars().nth(*linepos).unwrap_or('\0'); // Usa unwrap_or para devolver un carácter nulo si el índice está fuera de rango
-        *linepos += 1;
-        c
-    }
+// Cursor sobre el código fuente. Avanza carácter por carácter (UTF-8) llevando
+// la posición en bytes, la línea y la columna en caracteres, sin volver a recorrer el texto.
+// Las líneas avanzan únicamente con saltos de línea reales: '\n', '\r\n' o un '\r' aislado.
+struct Cursor<'a> {
+    chars: Chars<'a>,
+    pos: usize,  // Desplazamiento en bytes del siguiente carácter
+    line: usize, // Línea del siguiente carácter
+    col: usize,  // Columna (en caracteres) del siguiente carácter
+}
+
+// Posición guardada al iniciar un lexema
+#[derive(Clone, Copy)]
+struct Mark {
+    pos: usize,
+    line: usize,
+    col: usize,
 }
 
-// Función para retroceder un carácter en la línea actual
-fn unget_next_char(linepos: &mut usize) {
-    if *linepos > 0 {
-        *linepos -= 1;
+impl<'a> Cursor<'a> {
+    fn new(content: &'a str) -> Self {
+        Cursor {
+            chars: content.chars(),
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    // Devuelve el siguiente carácter sin consumirlo, o '\0' al final del archivo
+    fn peek(&self) -> char {
+        self.chars.clone().next().unwrap_or('\0')
+    }
+
+    // Consume y devuelve el siguiente carácter, o '\0' al final del archivo
+    fn bump(&mut self) -> char {
+        match self.chars.next() {
+            Some(c) => {
+                self.pos += c.len_utf8();
+                // En "\r\n" el salto se cuenta al consumir el '\n'
+                let ends_line = c == '\n' || (c == '\r' && self.peek() != '\n');
+                if ends_line {
+                    self.line += 1;
+                    self.col = 1;
+                } else if c != '\r' {
+                    self.col += 1;
+                }
+                c
+            }
+            None => '\0',
+        }
+    }
+
+    // Consume el siguiente carácter solo si coincide con el esperado
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == expected && expected != '\0' {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            pos: self.pos,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    // Construye el span del lexema que va desde `start` hasta la posición actual
+    fn span_from(&self, start: Mark) -> Span {
+        Span {
+            start_byte: start.pos,
+            end_byte: self.pos,
+            line: start.line,
+            end_line: self.line,
+            col_start: start.col,
+            col_end: self.col,
+        }
     }
 }
 
@@ -25,6 +91,7 @@ fn reserved_lookup(s: &str) -> TokenType {
         "else" => TokenType::ELSE,
         "do" => TokenType::DO,
         "while" => TokenType::WHILE,
+        "for" => TokenType::FOR,
         "repeat" => TokenType::REPEAT,
         "until" => TokenType::UNTIL,
         "read" => TokenType::READ,
@@ -36,335 +103,205 @@ fn reserved_lookup(s: &str) -> TokenType {
         "/*" => TokenType::InMultipleComment,
         "cin" => TokenType::CIN,
         "cout" => TokenType::COUT,
+        "switch" => TokenType::SWITCH,
+        "case" => TokenType::CASE,
+        "default" => TokenType::DEFAULT,
+        "break" => TokenType::BREAK,
+        "true" => TokenType::TRUE,
+        "false" => TokenType::FALSE,
         _ => TokenType::ID,
     }
 }
 
-// Función para realizar el análisis léxico y devolver los tokens
-pub fn get_token(
-    content: &str,
-) -> (
-    Vec<(TokenType, String, usize, usize)>,
-    Vec<(TokenType, String, usize, usize)>,
-) {
+// Diagnóstico para un carácter que no inicia ningún token del lenguaje
+fn unexpected_character(lexeme: &str, span: Span) -> Diagnostic {
+    let diagnostic = Diagnostic::error(
+        codes::UNEXPECTED_CHARACTER,
+        format!("carácter inesperado '{}'", lexeme),
+        span,
+    );
+    match lexeme {
+        "&" => diagnostic.with_help("el y lógico se escribe '&&'".to_string()),
+        "|" => diagnostic.with_help("el o lógico se escribe '||'".to_string()),
+        _ => diagnostic,
+    }
+}
+
+// Función para realizar el análisis léxico y devolver los tokens.
+// Los errores léxicos se registran en `diagnostics` y no producen tokens.
+pub fn get_token(content: &str, diagnostics: &mut Diagnostics) -> Vec<Token> {
     let mut tokens = Vec::new();
-    let mut errors = Vec::new();
-    let mut lineno = 1;
     let mut state = StateType::Start;
-    let mut token_string = String::new();
-    let mut linepos = 0;
-    let bufsize = content.len();
-    let mut column_number = 0;
-    while linepos <= bufsize {
-        let c = get_next_char(content, &mut linepos, bufsize);
+    let mut cursor = Cursor::new(content);
+    let mut start = cursor.mark(); // Inicio del lexema en curso
+    loop {
         match state {
             StateType::Start => {
-                if c == '\n' {
-                    lineno += 1;
-                    column_number = 1;
-                }
-                if c.is_whitespace() {
+                start = cursor.mark();
+                let c = cursor.bump();
+                if c == '\0' {
+                    state = StateType::EndFile;
+                } else if c.is_whitespace() {
                     // Ignorar espacios en blanco
-                    column_number += 1;
-                } else if c.is_ascii_alphabetic() || c == '_' {
+                } else if c.is_alphabetic() || c == '_' {
                     state = StateType::InId;
-                    token_string.push(c);
-                    column_number += 1;
-                } else if c.is_digit(10) {
+                } else if c.is_ascii_digit() {
                     state = StateType::InNum;
-                    token_string.push(c);
-                    column_number += 1;
                 } else if c == '/' {
-                    let next_char = get_next_char(content, &mut linepos, bufsize);
-                    if next_char == '/' {
-                        let next_char = get_next_char(content, &mut linepos, bufsize);
-                        if next_char == '\n' {
-                            lineno += 1;
-                        } else {
-                            unget_next_char(&mut linepos);
-                            state = StateType::InComment;
-                            lineno += 1;
-                        }
-                    } else if next_char == '*' {
-                        lineno += 1;
-                        let next_char = get_next_char(content, &mut linepos, bufsize);
-                        if next_char == '\n' {
-                            lineno += 1;
-                        } else {
-                            unget_next_char(&mut linepos);
-                            state = StateType::InMultiComment;
-                            lineno += 1;
-                        }
+                    if cursor.eat('/') {
+                        state = StateType::InComment;
+                    } else if cursor.eat('*') {
+                        state = StateType::InMultiComment;
                     } else {
-                        tokens.push((
-                            TokenType::DIVIDE,
-                            "/".to_string(),
-                            lineno,
-                            column_number - 1,
-                        ));
-                        unget_next_char(&mut linepos)
+                        tokens.push(Token::new(TokenType::DIVIDE, "/", cursor.span_from(start)));
                     }
                 } else {
-                    match c {
-                        '=' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((
-                                    TokenType::EQ,
-                                    "==".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                            } else {
-                                tokens.push((
-                                    TokenType::ASSIGN,
-                                    "=".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
-                            }
-                        }
-                        '!' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((
-                                    TokenType::NEQ,
-                                    "!=".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                            } else {
-                                errors.push((
-                                    TokenType::ERROR,
-                                    "!".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
-                            }
-                        }
-                        '<' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((
-                                    TokenType::LTE,
-                                    "<=".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                            } else {
-                                tokens.push((
-                                    TokenType::LT,
-                                    "<".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
-                            }
-                        }
-                        '>' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((
-                                    TokenType::GTE,
-                                    ">=".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                            } else {
-                                tokens.push((
-                                    TokenType::GT,
-                                    ">".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
-                            }
-                        }
-                        '+' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '+' {
-                                tokens.push((
-                                    TokenType::INCREMENT,
-                                    "++".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
+                    // Operadores que pueden formar un lexema de dos caracteres
+                    let pair = match c {
+                        '=' => Some(('=', TokenType::EQ, TokenType::ASSIGN)),
+                        '!' => Some(('=', TokenType::NEQ, TokenType::NOT)),
+                        '<' => Some(('=', TokenType::LTE, TokenType::LT)),
+                        '>' => Some(('=', TokenType::GTE, TokenType::GT)),
+                        '+' => Some(('+', TokenType::INCREMENT, TokenType::PLUS)),
+                        '-' => Some(('-', TokenType::DECREMENT, TokenType::MINUS)),
+                        // '&' y '|' solos no son operadores del lenguaje
+                        '&' => Some(('&', TokenType::AND, TokenType::ERROR)),
+                        '|' => Some(('|', TokenType::OR, TokenType::ERROR)),
+                        _ => None,
+                    };
+                    let kind = match pair {
+                        Some((second, double_kind, single_kind)) => {
+                            if cursor.eat(second) {
+                                double_kind
                             } else {
-                                tokens.push((
-                                    TokenType::PLUS,
-                                    "+".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
+                                single_kind
                             }
                         }
-                        '-' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '-' {
-                                tokens.push((
-                                    TokenType::DECREMENT,
-                                    "--".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                            } else {
-                                tokens.push((
-                                    TokenType::MINUS,
-                                    "-".to_string(),
-                                    lineno,
-                                    column_number - 1,
-                                ));
-                                unget_next_char(&mut linepos);
-                            }
-                        }
-                        '*' => {
-                            tokens.push((TokenType::TIMES, "*".to_string(), lineno, column_number))
-                        }
-                        '%' => {
-                            tokens.push((TokenType::MODULO, "%".to_string(), lineno, column_number))
-                        }
-                        '^' => {
-                            tokens.push((TokenType::POWER, "^".to_string(), lineno, column_number))
-                        }
-                        '(' => {
-                            tokens.push((TokenType::LPAREN, "(".to_string(), lineno, column_number))
-                        }
-                        ')' => {
-                            tokens.push((TokenType::RPAREN, ")".to_string(), lineno, column_number))
-                        }
-                        '{' => {
-                            tokens.push((TokenType::LBRACE, "{".to_string(), lineno, column_number))
-                        }
-                        '}' => {
-                            tokens.push((TokenType::RBRACE, "}".to_string(), lineno, column_number))
-                        }
-                        ',' => {
-                            tokens.push((TokenType::COMMA, ",".to_string(), lineno, column_number))
-                        }
-                        ';' => tokens.push((
-                            TokenType::SEMICOLON,
-                            ";".to_string(),
-                            lineno,
-                            column_number,
-                        )),
-                        '&' => {
-                            tokens.push((TokenType::AND, "&".to_string(), lineno, column_number))
-                        }
-                        '|' => tokens.push((TokenType::OR, "|".to_string(), lineno, column_number)),
-                        ':' => {
-                            tokens.push((TokenType::COLON, ":".to_string(), lineno, column_number))
-                        }
-                        '\0' => {
-                            state = StateType::EndFile;
-                        }
-                        _ => errors.push((
-                            TokenType::ERROR,
-                            c.to_string(),
-                            lineno,
-                            column_number - 1,
-                        )),
+                        None => match c {
+                            '*' => TokenType::TIMES,
+                            '%' => TokenType::MODULO,
+                            '^' => TokenType::POWER,
+                            '(' => TokenType::LPAREN,
+                            ')' => TokenType::RPAREN,
+                            '{' => TokenType::LBRACE,
+                            '}' => TokenType::RBRACE,
+                            ',' => TokenType::COMMA,
+                            ';' => TokenType::SEMICOLON,
+                            ':' => TokenType::COLON,
+                            _ => TokenType::ERROR,
+                        },
+                    };
+                    let lexeme = &content[start.pos..cursor.pos];
+                    if kind == TokenType::ERROR {
+                        diagnostics.push(unexpected_character(lexeme, cursor.span_from(start)));
+                    } else {
+                        tokens.push(Token::new(kind, lexeme, cursor.span_from(start)));
                     }
                 }
             }
             StateType::InId => {
-                if c.is_ascii_alphanumeric() || c == '_' {
-                    token_string.push(c);
+                let c = cursor.peek();
+                if c.is_alphanumeric() || c == '_' {
+                    cursor.bump();
                 } else {
-                    tokens.push((
-                        reserved_lookup(&token_string),
-                        token_string.clone(),
-                        lineno,
-                        (column_number - 1),
+                    let lexeme = &content[start.pos..cursor.pos];
+                    tokens.push(Token::new(
+                        reserved_lookup(lexeme),
+                        lexeme,
+                        cursor.span_from(start),
                     ));
-                    token_string.clear();
                     state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
                 }
             }
             StateType::InNum => {
-                if c.is_digit(10) {
-                    token_string.push(c);
+                let c = cursor.peek();
+                if c.is_ascii_digit() {
+                    cursor.bump();
                 } else if c == '.' {
+                    cursor.bump();
                     state = StateType::InReal;
-                    token_string.push(c);
                 } else {
-                    tokens.push((
+                    tokens.push(Token::new(
                         TokenType::NumInt,
-                        token_string.clone(),
-                        lineno,
-                        (column_number - 1),
+                        &content[start.pos..cursor.pos],
+                        cursor.span_from(start),
                     ));
-                    token_string.clear();
                     state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
                 }
             }
             StateType::InReal => {
-                if c.is_digit(10) {
-                    token_string.push(c);
-                } else if token_string.ends_with('.') {
-                    errors.push((
-                        TokenType::ERROR,
-                        token_string.clone(),
-                        lineno,
-                        (column_number - 1),
-                    ));
-                    token_string.clear();
-                    state = StateType::Start;
-                    unget_next_char(&mut linepos); //retornar un carácter
+                let c = cursor.peek();
+                if c.is_ascii_digit() {
+                    cursor.bump();
                 } else {
-                    tokens.push((
-                        TokenType::NumReal,
-                        token_string.clone(),
-                        lineno,
-                        (column_number - 1),
-                    ));
-                    token_string.clear();
+                    let lexeme = &content[start.pos..cursor.pos];
+                    // Un número que termina en '.' no tiene parte decimal
+                    if lexeme.ends_with('.') {
+                        diagnostics.push(
+                            Diagnostic::error(
+                                codes::MALFORMED_NUMBER,
+                                format!("número real mal formado '{}'", lexeme),
+                                cursor.span_from(start),
+                            )
+                            .with_help(format!(
+                                "agrega al menos un dígito después del punto: '{}0'",
+                                lexeme
+                            )),
+                        );
+                    } else {
+                        tokens.push(Token::new(
+                            TokenType::NumReal,
+                            lexeme,
+                            cursor.span_from(start),
+                        ));
+                    }
                     state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
                 }
             }
             StateType::InComment => {
-                if c == '\n' || c == '\0' {
+                // El salto de línea que cierra el comentario lo consume el estado Start
+                let c = cursor.peek();
+                if c == '\n' || c == '\r' || c == '\0' {
                     state = StateType::Start;
-                    column_number = 1;
+                } else {
+                    cursor.bump();
                 }
             }
             StateType::InMultiComment => {
+                let c = cursor.bump();
                 if c == '*' {
-                    lineno += 1;
-                    let next_char = get_next_char(content, &mut linepos, bufsize);
-                    if next_char == '/' {
+                    if cursor.eat('/') {
                         state = StateType::Start;
-                        lineno += 1;
-                    } else {
-                        unget_next_char(&mut linepos)
                     }
                 } else if c == '\0' {
-                    tokens.push((
-                        TokenType::InMultipleComment,
-                        "/*".to_string(),
-                        lineno,
-                        column_number - 1,
-                    ));
-                    println!("Error: '/*' Multiline comment not closed.");
+                    diagnostics.push(
+                        Diagnostic::error(
+                            codes::UNTERMINATED_COMMENT,
+                            "comentario de varias líneas sin cerrar".to_string(),
+                            Span {
+                                start_byte: start.pos,
+                                end_byte: start.pos + 2,
+                                line: start.line,
+                                end_line: start.line,
+                                col_start: start.col,
+                                col_end: start.col + 2,
+                            },
+                        )
+                        .with_help("cierra el comentario con '*/'".to_string()),
+                    );
                     state = StateType::EndFile;
                 }
             }
             StateType::EndFile => {
-                tokens.push((
+                tokens.push(Token::new(
                     TokenType::ENDFILE,
-                    "\0".to_string(),
-                    lineno,
-                    column_number - 1,
+                    "\0",
+                    cursor.span_from(cursor.mark()),
                 ));
-                break; // Salir del bucle while
+                break; // Salir del bucle
             }
             _ => (),
         }
     }
-    (tokens, errors)
+    tokens
 }
@@ -1,4 +1,8 @@
+pub mod analyze;
+pub mod debugger;
 pub mod globals;
+pub mod interpreter;
+pub mod ir;
 pub mod lexer;
 pub mod parser;
 pub mod symTab;
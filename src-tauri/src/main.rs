@@ -1,59 +1,402 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use app::globals::{log_error, NodeType, TokenType, TreeNode, ERRORS};
+use app::analyze::analyze;
+use app::debugger::{Breakpoints, DebugCommand, DebugEvent, Debugger};
+use app::globals::{Diagnostic, Diagnostics, NodeType, Severity, Token, TreeNode};
+use app::interpreter::{
+    BufferedConsole, ConsoleEvent, Interpreter, Limits, RunResult, StreamConsole,
+};
+use app::ir::{self, Quadruple};
 use app::lexer::get_token;
 use app::parser::parse_program;
+use app::symTab::{SymbolRecord, SymbolTable};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::fs;
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Programa que corre en segundo plano: el extremo del canal por el que le llega
+// lo que el usuario escribe en la terminal (si lee de ella) y la bandera para cancelarlo
+struct RunHandle {
+    input: Option<Sender<String>>,
+    cancel: Arc<AtomicBool>,
+    debug: Option<DebugHandle>,
+}
+
+// Programa que corre bajo el depurador: el canal de órdenes para reanudarlo y sus
+// breakpoints, que se pueden cambiar mientras corre
+struct DebugHandle {
+    commands: Sender<DebugCommand>,
+    breakpoints: Breakpoints,
+}
+
+#[derive(Default)]
+struct RunState {
+    current: Mutex<Option<RunHandle>>,
+    // Número de la última ejecución iniciada
+    last_run: AtomicU64,
+}
+
+impl RunState {
+    // Solo corre un programa a la vez: el anterior se cancela
+    fn replace(&self, handle: RunHandle) -> Result<(), String> {
+        let previous = self
+            .current
+            .lock()
+            .map_err(|err| err.to_string())?
+            .replace(handle);
+        if let Some(previous) = previous {
+            previous.cancel.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+// Datos de un evento de ejecución; `run` permite descartar los que aún llegan
+// de una ejecución ya reemplazada
+#[derive(Clone, Serialize)]
+struct RunEvent<T> {
+    run: u64,
+    data: T,
+}
+
 fn main() {
     tauri::Builder::default()
+        .manage(RunState::default())
         .invoke_handler(tauri::generate_handler![
             save_file,
             remove_file,
             lexic,
-            parse
+            parse,
+            semantic,
+            export_symbols,
+            intermediate,
+            export_intermediate,
+            run,
+            start_run,
+            send_input,
+            cancel_run,
+            start_debug,
+            debug_command,
+            set_breakpoints
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 #[tauri::command]
-fn lexic(
-    content: String,
-) -> Result<
-    (
-        Vec<(TokenType, String, usize, usize)>,
-        Vec<(TokenType, String, usize, usize)>,
-    ),
-    String,
-> {
-    Ok(get_token(&content))
+fn lexic(content: String) -> Result<(Vec<Token>, Vec<Diagnostic>), String> {
+    let mut diagnostics = Diagnostics::new();
+    let tokens = get_token(&content, &mut diagnostics);
+    Ok((tokens, diagnostics.into_vec()))
 }
 
 #[tauri::command]
-fn parse(
-    tokens: Vec<(TokenType, String, usize, usize)>,
-) -> Result<(TreeNode, Vec<String>), String> {
-    let mut errors_str: Vec<String> = Vec::new();
+fn parse(tokens: Vec<Token>) -> Result<(TreeNode, Vec<Diagnostic>), String> {
+    let mut diagnostics = Diagnostics::new();
     let mut current_token = 0;
-    let syntax_tree = match parse_program(&tokens, &mut current_token, &mut errors_str) {
+    let syntax_tree = match parse_program(&tokens, &mut current_token, &mut diagnostics) {
         Ok(tree) => tree,
         Err(err) => {
-            log_error(err.to_string());
+            diagnostics.push(*err);
             TreeNode::new(NodeType::Error)
         }
     };
-    let errors_from_global = {
-        let mut errors = ERRORS.lock().unwrap();
-        let errors_clone = errors.clone();
-        errors.clear(); // Clear errors after retrieval
-        errors_clone
+    Ok((syntax_tree, diagnostics.into_vec()))
+}
+
+// Compila el programa desde el código fuente: escáner, parser y análisis
+// semántico. Si hay errores léxicos o de sintaxis el árbol está incompleto: no se
+// analiza (no hay tabla de símbolos) y quedan los errores para que se sepa por qué.
+fn analyze_source(content: &str, diagnostics: &mut Diagnostics) -> (TreeNode, Option<SymbolTable>) {
+    let tokens = get_token(content, diagnostics);
+    let mut current_token = 0;
+    let mut syntax_tree = match parse_program(&tokens, &mut current_token, diagnostics) {
+        Ok(tree) => tree,
+        Err(err) => {
+            diagnostics.push(*err);
+            TreeNode::new(NodeType::Error)
+        }
+    };
+    if diagnostics.has_errors() {
+        return (syntax_tree, None);
+    }
+    let symbol_table = analyze(&mut syntax_tree, diagnostics);
+    (syntax_tree, Some(symbol_table))
+}
+
+// Árbol listo para ejecutarse con su tabla de símbolos, o los errores de
+// compilación que lo impiden
+fn compile(content: &str) -> Result<(TreeNode, SymbolTable), Vec<Diagnostic>> {
+    let mut diagnostics = Diagnostics::new();
+    match analyze_source(content, &mut diagnostics) {
+        (syntax_tree, Some(symbol_table)) if !diagnostics.has_errors() => {
+            Ok((syntax_tree, symbol_table))
+        }
+        _ => Err(diagnostics
+            .into_vec()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect()),
+    }
+}
+
+#[tauri::command]
+fn semantic(content: String) -> Result<(TreeNode, Vec<SymbolRecord>, Vec<Diagnostic>), String> {
+    let mut diagnostics = Diagnostics::new();
+    let (syntax_tree, symbol_table) = analyze_source(&content, &mut diagnostics);
+    let symbols = symbol_table
+        .map(|symbol_table| symbol_table.entries().collect())
+        .unwrap_or_default();
+    Ok((syntax_tree, symbols, diagnostics.into_vec()))
+}
+
+// Tabla de símbolos en formato "csv" o "json" para descargarla desde el IDE
+#[tauri::command]
+fn export_symbols(content: String, format: String) -> Result<String, String> {
+    let (_, symbol_table) = analyze_source(&content, &mut Diagnostics::new());
+    let symbol_table = symbol_table
+        .ok_or_else(|| "El programa tiene errores léxicos o de sintaxis".to_string())?;
+    match format.as_str() {
+        "csv" => Ok(symbol_table.to_csv()),
+        "json" => symbol_table.to_json().map_err(|err| err.to_string()),
+        _ => Err(format!("Formato de exportación desconocido: {}", format)),
+    }
+}
+
+// Código intermedio del programa en texto y como tabla de cuádruplas. Si hay
+// errores de compilación no se genera y se devuelven los errores.
+#[tauri::command]
+fn intermediate(content: String) -> (String, Vec<Quadruple>, Vec<Diagnostic>) {
+    match compile(&content) {
+        Ok((syntax_tree, _)) => {
+            let code = ir::generate(&syntax_tree);
+            (code.to_text(), code.quadruples, Vec::new())
+        }
+        Err(errors) => (String::new(), Vec::new(), errors),
+    }
+}
+
+// Código intermedio en formato "txt" o "json" para descargarlo desde el IDE
+#[tauri::command]
+fn export_intermediate(content: String, format: String) -> Result<String, String> {
+    let code = match compile(&content) {
+        Ok((syntax_tree, _)) => ir::generate(&syntax_tree),
+        Err(_) => return Err("El programa tiene errores de compilación".to_string()),
+    };
+    match format.as_str() {
+        "txt" => Ok(code.to_text()),
+        "json" => code.to_json().map_err(|err| err.to_string()),
+        _ => Err(format!("Formato de exportación desconocido: {}", format)),
+    }
+}
+
+// Compila y ejecuta el programa; `input` es la entrada que leen cin y read, una
+// línea por renglón. Si hay errores de compilación el programa no se ejecuta.
+// Corre fuera del hilo principal para no congelar el IDE y se detiene con `cancel_run`.
+#[tauri::command(async)]
+fn run(
+    state: tauri::State<RunState>,
+    content: String,
+    input: String,
+    limits: Option<Limits>,
+) -> Result<RunResult, String> {
+    let syntax_tree = match compile(&content) {
+        Ok((syntax_tree, _)) => syntax_tree,
+        Err(errors) => return Ok(RunResult::not_compiled(errors)),
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    state.replace(RunHandle {
+        input: None,
+        cancel: cancel.clone(),
+        debug: None,
+    })?;
+    let mut console = BufferedConsole::new(&input);
+    let result = Interpreter::new(&mut console)
+        .with_limits(limits.unwrap_or_default())
+        .with_cancel(cancel)
+        .run(&syntax_tree);
+    Ok(RunResult::finished(console.output, result))
+}
+
+// Ejecuta el programa en un hilo aparte y lo conecta con la terminal por eventos:
+// "run-output" con cada texto escrito, "run-input" cuando espera una línea (que
+// se envía con `send_input`) y "run-finished" con el resultado al terminar.
+// Sin `limits` se usan los límites por omisión. Devuelve el número de la ejecución.
+#[tauri::command]
+fn start_run(
+    window: tauri::Window,
+    state: tauri::State<RunState>,
+    content: String,
+    limits: Option<Limits>,
+) -> Result<u64, String> {
+    let run = state.last_run.fetch_add(1, Ordering::Relaxed) + 1;
+    let syntax_tree = match compile(&content) {
+        Ok((syntax_tree, _)) => syntax_tree,
+        Err(errors) => {
+            let data = RunResult::not_compiled(errors);
+            window
+                .emit("run-finished", RunEvent { run, data })
+                .map_err(|err| err.to_string())?;
+            return Ok(run);
+        }
     };
+    let (sender, receiver) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+    state.replace(RunHandle {
+        input: Some(sender),
+        cancel: cancel.clone(),
+        debug: None,
+    })?;
+
+    thread::spawn(move || {
+        let events = window.clone();
+        let mut console = StreamConsole::new(receiver, move |event| {
+            let _ = match event {
+                ConsoleEvent::Output(data) => events.emit("run-output", RunEvent { run, data }),
+                ConsoleEvent::InputRequested => events.emit("run-input", RunEvent { run, data: () }),
+            };
+        });
+        let result = Interpreter::new(&mut console)
+            .with_limits(limits.unwrap_or_default())
+            .with_cancel(cancel)
+            .run(&syntax_tree);
+        let data = RunResult::finished(console.output, result);
+        let _ = window.emit("run-finished", RunEvent { run, data });
+    });
+    Ok(run)
+}
+
+// Envía una línea escrita en la terminal al programa en ejecución
+#[tauri::command]
+fn send_input(state: tauri::State<RunState>, line: String) -> Result<(), String> {
+    let current = state.current.lock().map_err(|err| err.to_string())?;
+    match current.as_ref() {
+        Some(RunHandle {
+            input: Some(input), ..
+        }) => input
+            .send(line)
+            .map_err(|_| "El programa ya terminó".to_string()),
+        Some(_) => Err("El programa no lee de la terminal".to_string()),
+        None => Err("No hay ningún programa en ejecución".to_string()),
+    }
+}
 
-    // Combine local and global errors
-    errors_str.extend(errors_from_global.iter().cloned());
-    Ok((syntax_tree, errors_str))
+// Ejecuta el programa bajo el depurador en un hilo aparte. Además de "run-output"
+// y "run-input" emite "paused" con la sentencia y las variables cada vez que se
+// detiene, "resumed" al reanudarse con `debug_command` y "terminated" con el
+// resultado al terminar. `breakpoints` son números de línea; con `stop_on_entry`
+// se detiene antes de la primera sentencia. Devuelve el número de la ejecución.
+#[tauri::command]
+fn start_debug(
+    window: tauri::Window,
+    state: tauri::State<RunState>,
+    content: String,
+    breakpoints: Vec<usize>,
+    stop_on_entry: bool,
+) -> Result<u64, String> {
+    let run = state.last_run.fetch_add(1, Ordering::Relaxed) + 1;
+    let (syntax_tree, symbol_table) = match compile(&content) {
+        Ok(compiled) => compiled,
+        Err(errors) => {
+            let data = RunResult::not_compiled(errors);
+            window
+                .emit("terminated", RunEvent { run, data })
+                .map_err(|err| err.to_string())?;
+            return Ok(run);
+        }
+    };
+    let (sender, receiver) = mpsc::channel();
+    let (commands, command_receiver) = mpsc::channel();
+    let breakpoints: Breakpoints = Arc::new(Mutex::new(breakpoints.into_iter().collect()));
+    let cancel = Arc::new(AtomicBool::new(false));
+    state.replace(RunHandle {
+        input: Some(sender),
+        cancel: cancel.clone(),
+        debug: Some(DebugHandle {
+            commands,
+            breakpoints: breakpoints.clone(),
+        }),
+    })?;
+
+    thread::spawn(move || {
+        let events = window.clone();
+        let mut console = StreamConsole::new(receiver, move |event| {
+            let _ = match event {
+                ConsoleEvent::Output(data) => events.emit("run-output", RunEvent { run, data }),
+                ConsoleEvent::InputRequested => events.emit("run-input", RunEvent { run, data: () }),
+            };
+        });
+        let events = window.clone();
+        let mut debugger = Debugger::new(
+            symbol_table,
+            breakpoints,
+            command_receiver,
+            stop_on_entry,
+            move |event| {
+                let _ = match event {
+                    DebugEvent::Paused(data) => events.emit("paused", RunEvent { run, data }),
+                    DebugEvent::Resumed => events.emit("resumed", RunEvent { run, data: () }),
+                };
+            },
+        );
+        let result = Interpreter::new(&mut console)
+            .with_cancel(cancel)
+            .with_observer(&mut debugger)
+            .run(&syntax_tree);
+        let data = RunResult::finished(console.output, result);
+        let _ = window.emit("terminated", RunEvent { run, data });
+    });
+    Ok(run)
+}
+
+// Reanuda el programa detenido en el depurador
+#[tauri::command]
+fn debug_command(state: tauri::State<RunState>, command: DebugCommand) -> Result<(), String> {
+    let current = state.current.lock().map_err(|err| err.to_string())?;
+    match current.as_ref().and_then(|handle| handle.debug.as_ref()) {
+        Some(debug) => debug
+            .commands
+            .send(command)
+            .map_err(|_| "El programa ya terminó".to_string()),
+        None => Err("No hay ningún programa en depuración".to_string()),
+    }
+}
+
+// Reemplaza los breakpoints del programa en depuración por las líneas dadas
+#[tauri::command]
+fn set_breakpoints(state: tauri::State<RunState>, lines: Vec<usize>) -> Result<(), String> {
+    let current = state.current.lock().map_err(|err| err.to_string())?;
+    match current.as_ref().and_then(|handle| handle.debug.as_ref()) {
+        Some(debug) => {
+            let mut breakpoints = debug.breakpoints.lock().map_err(|err| err.to_string())?;
+            *breakpoints = lines.into_iter().collect::<HashSet<usize>>();
+            Ok(())
+        }
+        None => Err("No hay ningún programa en depuración".to_string()),
+    }
+}
+
+// Detiene el programa en ejecución o en depuración. Termina con un error que
+// indica la línea en la que iba, y llega como siempre en el evento "run-finished"
+// (o "terminated" si se estaba depurando).
+#[tauri::command]
+fn cancel_run(state: tauri::State<RunState>) -> Result<(), String> {
+    // Al descartar los emisores también se despierta un cin que espera entrada o
+    // un depurador detenido que espera una orden
+    let handle = state.current.lock().map_err(|err| err.to_string())?.take();
+    match handle {
+        Some(handle) => {
+            handle.cancel.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err("No hay ningún programa en ejecución".to_string()),
+    }
 }
 
 #[tauri::command]
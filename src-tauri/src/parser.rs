@@ -1,660 +1,1278 @@
-use crate::globals::{log_error, NodeType, TokenType, TreeNode};
+use crate::globals::{
+    codes, Diagnostic, DiagnosticResult, Diagnostics, NodeType, Span, Token, TokenType, TreeNode,
+};
+
+// Resultado de una regla del parser
+pub type ParseResult<T> = DiagnosticResult<T>;
+
+// Span del token indicado, o del último si ya no quedan tokens
+fn span_at(tokens: &[Token], index: usize) -> Span {
+    tokens
+        .get(index)
+        .or(tokens.last())
+        .map(|token| token.span.clone())
+        .unwrap_or_default()
+}
+
+// Span que cubre los tokens consumidos entre `start` y `end` (exclusivo)
+fn span_between(tokens: &[Token], start: usize, end: usize) -> Span {
+    let first = span_at(tokens, start);
+    if end <= start {
+        return first;
+    }
+    first.to(&span_at(tokens, end - 1))
+}
+
+// Describe el token encontrado en la posición indicada para los mensajes de error
+fn found(tokens: &[Token], index: usize) -> String {
+    match tokens.get(index) {
+        Some(token) if token.kind != TokenType::ENDFILE => format!("'{}'", token.lexeme),
+        _ => "el fin del archivo".to_string(),
+    }
+}
+
+// Forma en que se escribe en el código un token esperado
+fn token_text(kind: &TokenType) -> String {
+    let text = match kind {
+        TokenType::LPAREN => "(",
+        TokenType::RPAREN => ")",
+        TokenType::LBRACE => "{",
+        TokenType::RBRACE => "}",
+        TokenType::SEMICOLON => ";",
+        TokenType::COLON => ":",
+        TokenType::COMMA => ",",
+        TokenType::ASSIGN => "=",
+        TokenType::IF => "if",
+        TokenType::ELSE => "else",
+        TokenType::DO => "do",
+        TokenType::WHILE => "while",
+        TokenType::FOR => "for",
+        TokenType::REPEAT => "repeat",
+        TokenType::UNTIL => "until",
+        TokenType::READ => "read",
+        TokenType::WRITE => "write",
+        TokenType::INTEGER => "int",
+        TokenType::DOUBLE => "double",
+        TokenType::MAIN => "main",
+        TokenType::RETURN => "return",
+        TokenType::CIN => "cin",
+        TokenType::COUT => "cout",
+        TokenType::SWITCH => "switch",
+        TokenType::CASE => "case",
+        TokenType::DEFAULT => "default",
+        TokenType::BREAK => "break",
+        _ => return format!("{:?}", kind),
+    };
+    format!("'{}'", text)
+}
+
+// Error de sintaxis: se esperaba `what` en la posición `index`
+fn expected(tokens: &[Token], index: usize, what: &str) -> Box<Diagnostic> {
+    Box::new(Diagnostic::error(
+        codes::EXPECTED_TOKEN,
+        format!(
+            "se esperaba {} pero se encontró {}",
+            what,
+            found(tokens, index)
+        ),
+        span_at(tokens, index),
+    ))
+}
+
+// Error de sintaxis: el token en la posición `index` no puede aparecer ahí
+fn unexpected(tokens: &[Token], index: usize) -> Box<Diagnostic> {
+    Box::new(Diagnostic::error(
+        codes::UNEXPECTED_TOKEN,
+        format!("token inesperado {}", found(tokens, index)),
+        span_at(tokens, index),
+    ))
+}
 
 fn match_token(
-    tokens: &[(TokenType, String, usize, usize)],
-    expected: TokenType,
+    tokens: &[Token],
+    expected_kind: TokenType,
     current_token: &mut usize,
-) -> Result<(), String> {
-    if *current_token < tokens.len() && tokens[*current_token].0 == expected {
+) -> ParseResult<()> {
+    if *current_token < tokens.len() && tokens[*current_token].kind == expected_kind {
         *current_token += 1;
         Ok(())
     } else {
-        println!("token in match: {:?}", tokens.get(*current_token));
-        Err(format!(
-            "Error de sintaxis: se esperaba {:?} en la posición {:?}",
-            expected,
-            tokens.get(*current_token)
+        Err(expected(
+            tokens,
+            *current_token,
+            &token_text(&expected_kind),
         ))
     }
 }
 
 pub fn parse_program(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-    errors: &mut Vec<String>,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut root = TreeNode::new(NodeType::MainRoot);
-    while *current_token < tokens.len() && tokens[*current_token].0 != TokenType::ENDFILE {
-        match parse_statement(tokens, current_token) {
+    while *current_token < tokens.len() && tokens[*current_token].kind != TokenType::ENDFILE {
+        let start = *current_token;
+        match parse_statement(tokens, current_token, diagnostics) {
             Ok(statement_node) => root.children.push(statement_node),
-            Err(err) => errors.push(err.to_string()), // Convertir el error en una cadena antes de agregarlo al vector
+            Err(err) => recover(tokens, current_token, start, *err, diagnostics),
         }
     }
 
+    root.span = span_between(tokens, start, *current_token);
     Ok(root)
 }
 fn parse_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    match tokens.get(*current_token) {
-        Some((TokenType::ID, _, _, _)) => {
-            if let Some((TokenType::INCREMENT, _, _, _)) = tokens.get(*current_token + 1) {
-                return parse_increment_statement(tokens, current_token);
-            } else if let Some((TokenType::DECREMENT, _, _, _)) = tokens.get(*current_token + 1) {
-                return parse_decrement_statement(tokens, current_token);
-            }
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    if let Some(Token {
+        kind: TokenType::ID,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        if let Some(Token {
+            kind: TokenType::INCREMENT,
+            ..
+        }) = tokens.get(*current_token + 1)
+        {
+            return parse_increment_statement(tokens, current_token);
+        } else if let Some(Token {
+            kind: TokenType::DECREMENT,
+            ..
+        }) = tokens.get(*current_token + 1)
+        {
+            return parse_decrement_statement(tokens, current_token);
         }
-        _ => {}
     }
 
     match tokens.get(*current_token) {
-        Some((TokenType::COLON, _, _, _)) => {
+        Some(Token {
+            kind: TokenType::COLON,
+            ..
+        }) => {
             *current_token += 1;
-            return Err("Error de sintaxis: token fuera de un case ':'".to_string());
+            return Err(Box::new(Diagnostic::error(
+                codes::OUTSIDE_SWITCH,
+                "':' fuera de un case".to_string(),
+                span_at(tokens, *current_token - 1),
+            )));
+        }
+        Some(Token {
+            kind: TokenType::CASE | TokenType::DEFAULT,
+            lexeme,
+            ..
+        }) => {
+            let diagnostic = Diagnostic::error(
+                codes::OUTSIDE_SWITCH,
+                format!("'{}' fuera de un switch", lexeme),
+                span_at(tokens, *current_token),
+            );
+            *current_token += 1;
+            return Err(Box::new(diagnostic));
         }
         _ => {}
     }
 
     match tokens.get(*current_token) {
-        Some((TokenType::IF, _, _, _)) => return parse_if_statement(tokens, current_token),
-        Some((TokenType::WHILE, _, _, _)) => return parse_while_statement(tokens, current_token),
-        Some((TokenType::WRITE, _, _, _)) => return parse_write_statement(tokens, current_token),
-        Some((TokenType::READ, _, _, _)) => return parse_read_statement(tokens, current_token),
-        Some((TokenType::DO, _, _, _)) => return parse_do_while_statement(tokens, current_token),
-        Some((TokenType::REPEAT, _, _, _)) => {
-            return parse_repeat_until_statement(tokens, current_token)
-        }
-        Some((TokenType::RETURN, _, _, _)) => return parse_return_statement(tokens, current_token),
-        Some((TokenType::CIN, _, _, _)) => return parse_cin_statement(tokens, current_token),
-        Some((TokenType::COUT, _, _, _)) => return parse_cout_statement(tokens, current_token),
-        Some((TokenType::MAIN, _, _, _)) => return parse_main_function(tokens, current_token),
-        Some((TokenType::INTEGER, _, _, _)) => {
-            return parse_int_variable_declaration(tokens, current_token)
-        }
-        Some((TokenType::DOUBLE, _, _, _)) => {
-            return parse_double_variable_declaration(tokens, current_token)
-        }
-        Some((TokenType::ID, _, _, _)) => {
-            let assignment_node = parse_assignment(tokens, current_token)?;
-            if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+        Some(Token {
+            kind: TokenType::LBRACE,
+            ..
+        }) => Ok(parse_block(tokens, current_token, diagnostics)),
+        Some(Token {
+            kind: TokenType::IF,
+            ..
+        }) => parse_if_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::WHILE,
+            ..
+        }) => parse_while_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::FOR,
+            ..
+        }) => parse_for_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::SWITCH,
+            ..
+        }) => parse_switch_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::BREAK,
+            ..
+        }) => parse_break_statement(tokens, current_token),
+        Some(Token {
+            kind: TokenType::WRITE,
+            ..
+        }) => parse_write_statement(tokens, current_token),
+        Some(Token {
+            kind: TokenType::READ,
+            ..
+        }) => parse_read_statement(tokens, current_token),
+        Some(Token {
+            kind: TokenType::DO,
+            ..
+        }) => parse_do_while_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::REPEAT,
+            ..
+        }) => parse_repeat_until_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::RETURN,
+            ..
+        }) => parse_return_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::CIN,
+            ..
+        }) => parse_cin_statement(tokens, current_token),
+        Some(Token {
+            kind: TokenType::COUT,
+            ..
+        }) => parse_cout_statement(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::MAIN,
+            ..
+        }) => parse_main_function(tokens, current_token, diagnostics),
+        Some(Token {
+            kind: TokenType::INTEGER,
+            ..
+        }) => parse_variable_declaration(
+            tokens,
+            current_token,
+            diagnostics,
+            TokenType::INTEGER,
+            NodeType::IntStatement,
+        ),
+        Some(Token {
+            kind: TokenType::DOUBLE,
+            ..
+        }) => parse_variable_declaration(
+            tokens,
+            current_token,
+            diagnostics,
+            TokenType::DOUBLE,
+            NodeType::DoubleStatement,
+        ),
+        Some(Token {
+            kind: TokenType::ID,
+            ..
+        }) => {
+            let assignment_node = parse_assignment(tokens, current_token, diagnostics)?;
+            if let Some(Token {
+                kind: TokenType::SEMICOLON,
+                ..
+            }) = tokens.get(*current_token)
+            {
                 *current_token += 1;
-                return Ok(assignment_node);
+                Ok(assignment_node)
             } else {
-                return Err(format!(
-                    "Error de sintaxis: se esperaba ';' en la posición {:?}",
-                    *current_token
-                ));
+                Err(expected(tokens, *current_token, "';'"))
             }
         }
         _ => {
             if is_part_of_expression(tokens, current_token) {
-                println!("token: {:?}", tokens.get(*current_token));
-                return Err(format!("Error de sintaxis: se esperaba una asignación a un identificador antes de la posición {:?}", tokens.get(*current_token)));
+                Err(Box::new(
+                    Diagnostic::error(
+                        codes::EXPRESSION_WITHOUT_ASSIGNMENT,
+                        "la expresión no se asigna a ningún identificador".to_string(),
+                        span_at(tokens, *current_token),
+                    )
+                    .with_help("asigna el resultado a una variable: x = ...;".to_string()),
+                ))
             } else {
-                return Err(format!(
-                    "Error de sintaxis: token inesperado {:?}",
-                    tokens.get(*current_token)
-                ));
+                Err(unexpected(tokens, *current_token))
             }
         }
     }
 }
 
-fn is_part_of_expression(
-    tokens: &[(TokenType, String, usize, usize)],
+// Bloque delimitado por llaves con cero o más sentencias
+fn parse_block(
+    tokens: &[Token],
     current_token: &mut usize,
-) -> bool {
-    if parse_expression(tokens, current_token).is_ok() {
-        return true;
+    diagnostics: &mut Diagnostics,
+) -> TreeNode {
+    let start = *current_token;
+    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
+        diagnostics.push(*err);
     }
-    false
+    let mut node = parse_statement_list(tokens, current_token, diagnostics, &[TokenType::RBRACE]);
+    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
+        diagnostics.push(*err);
+    }
+    // El bloque incluye sus llaves
+    node.span = span_between(tokens, start, *current_token);
+    node
 }
 
-fn parse_int_variable_declaration(
-    tokens: &[(TokenType, String, usize, usize)],
+// Lista de sentencias que termina antes de cualquiera de los tokens indicados (o del fin de archivo)
+fn parse_statement_list(
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::IntStatement);
+    diagnostics: &mut Diagnostics,
+    terminators: &[TokenType],
+) -> TreeNode {
+    let first = *current_token;
+    let mut node = TreeNode::new(NodeType::Block);
+    while let Some(token) = tokens.get(*current_token) {
+        if token.kind == TokenType::ENDFILE || terminators.contains(&token.kind) {
+            break;
+        }
+        let start = *current_token;
+        match parse_statement(tokens, current_token, diagnostics) {
+            Ok(statement_node) => node.children.push(statement_node),
+            Err(err) => recover(tokens, current_token, start, *err, diagnostics),
+        }
+    }
+    node.span = span_between(tokens, first, *current_token);
+    node
+}
 
-    // Parsear la palabra clave 'int'
-    match_token(tokens, TokenType::INTEGER, current_token)?;
+// Tokens con los que puede empezar una sentencia; el modo pánico se detiene en ellos
+fn starts_statement(kind: &TokenType) -> bool {
+    matches!(
+        kind,
+        TokenType::IF
+            | TokenType::DO
+            | TokenType::WHILE
+            | TokenType::FOR
+            | TokenType::REPEAT
+            | TokenType::SWITCH
+            | TokenType::CASE
+            | TokenType::DEFAULT
+            | TokenType::BREAK
+            | TokenType::READ
+            | TokenType::WRITE
+            | TokenType::CIN
+            | TokenType::COUT
+            | TokenType::RETURN
+            | TokenType::INTEGER
+            | TokenType::DOUBLE
+            | TokenType::MAIN
+    )
+}
 
-    // Parsear los identificadores
-    loop {
-        match tokens.get(*current_token) {
-            Some((TokenType::ID, id, _, _)) => {
-                node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(TokenType::ID),
-                    value: Some(id.clone()),
-                    children: Vec::new(),
-                });
+// Modo pánico: descarta tokens hasta un punto seguro. Un ';' se consume y un '}'
+// se deja para el bloque que lo espera; un bloque anidado se salta completo y
+// termina la sentencia rota.
+fn synchronize(tokens: &[Token], current_token: &mut usize) {
+    let mut depth = 0;
+    while let Some(token) = tokens.get(*current_token) {
+        match token.kind {
+            TokenType::ENDFILE => return,
+            TokenType::LBRACE => depth += 1,
+            TokenType::RBRACE if depth == 0 => return,
+            TokenType::RBRACE if depth == 1 => {
                 *current_token += 1;
-                if let Some((TokenType::COMMA, _, _, _)) = tokens.get(*current_token) {
-                    *current_token += 1; // Avanzar si hay una coma
-                } else {
-                    break; // Salir del bucle si no hay más identificadores
-                }
+                return;
             }
-            _ => {
-                return Err(format!(
-                    "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-                    tokens.get(*current_token)
-                ))
+            TokenType::RBRACE => depth -= 1,
+            TokenType::SEMICOLON if depth == 0 => {
+                *current_token += 1;
+                return;
             }
+            ref kind if depth == 0 && starts_statement(kind) => return,
+            _ => {}
         }
+        *current_token += 1;
+    }
+}
+
+// Registra el error de una sentencia y reanuda el análisis en la siguiente
+fn recover(
+    tokens: &[Token],
+    current_token: &mut usize,
+    start: usize,
+    err: Diagnostic,
+    diagnostics: &mut Diagnostics,
+) {
+    diagnostics.push(err);
+    // Descartar al menos un token si la sentencia no consumió nada para no ciclarse
+    if *current_token == start {
+        *current_token += 1;
     }
+    synchronize(tokens, current_token);
+}
+
+// Prueba si en la posición actual empieza una expresión sin consumir tokens
+fn is_part_of_expression(tokens: &[Token], current_token: &usize) -> bool {
+    let mut probe = *current_token;
+    parse_expression(tokens, &mut probe, &mut Diagnostics::new()).is_ok()
+}
+
+// Declaración de variables: el tipo seguido de uno o más declaradores separados
+// por comas, p. ej. int x = 5, y = x + 1;
+fn parse_variable_declaration(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+    type_token: TokenType,
+    node_type: NodeType,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = parse_declarators(tokens, current_token, diagnostics, type_token, node_type)?;
 
     // Verificar si hay un punto y coma al final
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1; // Avanzar si hay un punto y coma
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ))
+        Err(expected(tokens, *current_token, "';'"))
     }
 }
 
-fn parse_double_variable_declaration(
-    tokens: &[(TokenType, String, usize, usize)],
+// El tipo y sus declaradores, sin el ';' final
+fn parse_declarators(
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::DoubleStatement);
+    diagnostics: &mut Diagnostics,
+    type_token: TokenType,
+    node_type: NodeType,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(node_type);
 
-    match_token(tokens, TokenType::DOUBLE, current_token)?;
+    // Parsear la palabra clave 'int' o 'double'
+    match_token(tokens, type_token, current_token)?;
+
+    // Parsear los declaradores
     loop {
-        match tokens.get(*current_token) {
-            Some((TokenType::ID, id, _, _)) => {
-                node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(TokenType::ID),
-                    value: Some(id.clone()),
-                    children: Vec::new(),
-                });
-                *current_token += 1;
-                if let Some((TokenType::COMMA, _, _, _)) = tokens.get(*current_token) {
-                    *current_token += 1; // Avanzar si hay una coma
-                } else {
-                    break; // Salir del bucle si no hay más identificadores
-                }
-            }
-            _ => {
-                return Err(format!(
-                    "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-                    tokens.get(*current_token)
-                ))
-            }
+        node.children
+            .push(parse_declarator(tokens, current_token, diagnostics)?);
+        if let Some(Token {
+            kind: TokenType::COMMA,
+            ..
+        }) = tokens.get(*current_token)
+        {
+            *current_token += 1; // Avanzar si hay una coma
+        } else {
+            break; // Salir del bucle si no hay más declaradores
         }
     }
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
 
-    // Verificar si hay un punto y coma al final
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1; // Avanzar si hay un punto y coma
-        Ok(node)
-    } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ))
+// Declarador: identificador con un inicializador opcional (x o x = expresión)
+fn parse_declarator(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::Declarator);
+    match tokens.get(*current_token) {
+        Some(Token {
+            kind: TokenType::ID,
+            lexeme: id,
+            ..
+        }) => {
+            node.token = Some(TokenType::ID);
+            node.value = Some(id.clone());
+            *current_token += 1;
+        }
+        _ => return Err(expected(tokens, *current_token, "un identificador")),
+    }
+    if let Some(Token {
+        kind: TokenType::ASSIGN,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        *current_token += 1;
+        let initializer_node = parse_expression(tokens, current_token, diagnostics)?;
+        node.children.push(initializer_node);
     }
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
 }
 
 fn parse_if_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::IfStatement);
     match_token(tokens, TokenType::IF, current_token)?;
-    let condition_node = parse_expression(tokens, current_token)?;
+    let condition_node = parse_expression(tokens, current_token, diagnostics)?;
     node.children.push(condition_node);
-    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
-        }
-    }
-    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    if let Some((TokenType::ELSE, _, _, _)) = tokens.get(*current_token) {
-        let else_node = parse_else_statement(tokens, current_token);
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    if let Some(Token {
+        kind: TokenType::ELSE,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        let else_node = parse_else_statement(tokens, current_token, diagnostics);
         match else_node {
             Ok(else_node) => {
                 node.children.push(else_node);
             }
             Err(err) => {
-                log_error(err.to_string());
+                diagnostics.push(*err);
             }
         }
     }
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
 fn parse_else_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::ElseStatement);
     match_token(tokens, TokenType::ELSE, current_token)?;
-    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
-        }
-    }
-    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
 fn parse_do_while_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::DoWhileStatement);
     match_token(tokens, TokenType::DO, current_token)?;
-    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
-        }
-    }
-    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
     if let Err(err) = match_token(tokens, TokenType::WHILE, current_token) {
-        log_error(err.to_string());
+        diagnostics.push(*err);
     }
-    let condition_node = parse_expression(tokens, current_token)?;
+    let condition_node = parse_expression(tokens, current_token, diagnostics)?;
     node.children.push(condition_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ));
+        return Err(expected(tokens, *current_token, "';'"));
     }
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
 fn parse_while_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::WhileStatement);
     match_token(tokens, TokenType::WHILE, current_token)?;
-    let condition_node = parse_expression(tokens, current_token)?;
+    let condition_node = parse_expression(tokens, current_token, diagnostics)?;
     node.children.push(condition_node);
-    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+// for (inicialización; condición; actualización) { ... }. La inicialización puede
+// declarar variables, que solo existen dentro del ciclo. Cualquiera de las tres
+// partes puede ir vacía: la inicialización y la actualización quedan como bloques
+// vacíos y la condición vacía vale true.
+fn parse_for_statement(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::ForStatement);
+    match_token(tokens, TokenType::FOR, current_token)?;
+    match_token(tokens, TokenType::LPAREN, current_token)?;
+    let init_node = if at(tokens, *current_token, &TokenType::SEMICOLON) {
+        empty_clause(tokens, *current_token)
+    } else {
+        parse_for_init(tokens, current_token, diagnostics)?
+    };
+    node.children.push(init_node);
+    match_token(tokens, TokenType::SEMICOLON, current_token)?;
+    let condition_node = if at(tokens, *current_token, &TokenType::SEMICOLON) {
+        TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::TRUE,
+            "true".to_string(),
+            span_at(tokens, *current_token),
+        )
+    } else {
+        parse_expression(tokens, current_token, diagnostics)?
+    };
+    node.children.push(condition_node);
+    match_token(tokens, TokenType::SEMICOLON, current_token)?;
+    let update_node = if at(tokens, *current_token, &TokenType::RPAREN) {
+        empty_clause(tokens, *current_token)
+    } else {
+        parse_for_update(tokens, current_token, diagnostics)?
+    };
+    node.children.push(update_node);
+    if let Err(err) = match_token(tokens, TokenType::RPAREN, current_token) {
+        diagnostics.push(*err);
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
-        }
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+// Indica si el token en la posición `index` es del tipo dado
+fn at(tokens: &[Token], index: usize, kind: &TokenType) -> bool {
+    matches!(tokens.get(index), Some(token) if token.kind == *kind)
+}
+
+// Parte vacía de un for: un bloque sin sentencias en la posición del token que la cierra
+fn empty_clause(tokens: &[Token], index: usize) -> TreeNode {
+    let mut node = TreeNode::new(NodeType::Block);
+    node.span = span_at(tokens, index);
+    node
+}
+
+// Inicialización de un for: declaración o asignación sin ';' final
+fn parse_for_init(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    match tokens.get(*current_token).map(|token| &token.kind) {
+        Some(TokenType::INTEGER) => parse_declarators(
+            tokens,
+            current_token,
+            diagnostics,
+            TokenType::INTEGER,
+            NodeType::IntStatement,
+        ),
+        Some(TokenType::DOUBLE) => parse_declarators(
+            tokens,
+            current_token,
+            diagnostics,
+            TokenType::DOUBLE,
+            NodeType::DoubleStatement,
+        ),
+        _ => parse_assignment(tokens, current_token, diagnostics),
     }
-    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+}
+
+// Actualización de un for: asignación, incremento o decremento sin ';' final
+fn parse_for_update(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let node_type = match tokens.get(*current_token + 1) {
+        Some(Token {
+            kind: TokenType::INCREMENT,
+            ..
+        }) => NodeType::Increment,
+        Some(Token {
+            kind: TokenType::DECREMENT,
+            ..
+        }) => NodeType::Decrement,
+        _ => return parse_assignment(tokens, current_token, diagnostics),
+    };
+    match tokens.get(*current_token) {
+        Some(Token {
+            kind: TokenType::ID,
+            lexeme: id,
+            ..
+        }) => {
+            let mut node = TreeNode::new(node_type);
+            node.children.push(TreeNode::leaf(
+                NodeType::Factor,
+                TokenType::ID,
+                id.clone(),
+                span_at(tokens, *current_token),
+            ));
+            node.span = span_between(tokens, *current_token, *current_token + 2);
+            *current_token += 2;
+            Ok(node)
+        }
+        _ => Err(expected(tokens, *current_token, "un identificador")),
     }
-    Ok(node)
 }
 
 fn parse_repeat_until_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::RepeatUntilStatement);
     match_token(tokens, TokenType::REPEAT, current_token)?;
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    if let Err(err) = match_token(tokens, TokenType::UNTIL, current_token) {
+        diagnostics.push(*err);
+    }
+    let condition_node = parse_expression(tokens, current_token, diagnostics)?;
+    node.children.push(condition_node);
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        *current_token += 1;
+    } else {
+        return Err(expected(tokens, *current_token, "';'"));
+    }
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+fn parse_switch_statement(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::SwitchStatement);
+    match_token(tokens, TokenType::SWITCH, current_token)?;
+    let selector_node = parse_expression(tokens, current_token, diagnostics)?;
+    node.children.push(selector_node);
     if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+        diagnostics.push(*err);
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
+    loop {
+        match tokens.get(*current_token) {
+            Some(Token {
+                kind: TokenType::CASE,
+                ..
+            }) => match parse_case_statement(tokens, current_token, diagnostics) {
+                Ok(case_node) => node.children.push(case_node),
+                Err(err) => {
+                    diagnostics.push(*err);
+                    skip_to_switch_label(tokens, current_token);
+                }
+            },
+            Some(Token {
+                kind: TokenType::DEFAULT,
+                ..
+            }) => match parse_default_statement(tokens, current_token, diagnostics) {
+                Ok(default_node) => node.children.push(default_node),
+                Err(err) => {
+                    diagnostics.push(*err);
+                    skip_to_switch_label(tokens, current_token);
+                }
+            },
+            Some(Token {
+                kind: TokenType::RBRACE | TokenType::ENDFILE,
+                ..
+            })
+            | None => break,
+            Some(_) => {
+                // Un solo error por tramo inválido
+                diagnostics.push(*expected(tokens, *current_token, "'case' o 'default'"));
+                skip_to_switch_label(tokens, current_token);
+            }
         }
     }
     if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+        diagnostics.push(*err);
     }
-    if let Err(err) = match_token(tokens, TokenType::UNTIL, current_token) {
-        log_error(err.to_string());
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+// Descarta tokens hasta el siguiente 'case', 'default' o el '}' del switch
+fn skip_to_switch_label(tokens: &[Token], current_token: &mut usize) {
+    while let Some(token) = tokens.get(*current_token) {
+        match token.kind {
+            TokenType::CASE | TokenType::DEFAULT | TokenType::RBRACE | TokenType::ENDFILE => return,
+            _ => *current_token += 1,
+        }
     }
-    let condition_node = parse_expression(tokens, current_token)?;
-    node.children.push(condition_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+}
+fn parse_case_statement(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::CaseStatement);
+    match_token(tokens, TokenType::CASE, current_token)?;
+    node.children.push(parse_case_label(tokens, current_token)?);
+    match_token(tokens, TokenType::COLON, current_token)?;
+    node.children.push(parse_statement_list(
+        tokens,
+        current_token,
+        diagnostics,
+        &[TokenType::CASE, TokenType::DEFAULT, TokenType::RBRACE],
+    ));
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+// Constante numérica de un case, con signo opcional: case -1:
+fn parse_case_label(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let sign = match tokens.get(*current_token) {
+        Some(Token {
+            kind: kind @ (TokenType::MINUS | TokenType::PLUS),
+            lexeme,
+            ..
+        }) => {
+            let sign = TreeNode::leaf(
+                NodeType::Factor,
+                kind.clone(),
+                lexeme.clone(),
+                span_at(tokens, *current_token),
+            );
+            *current_token += 1;
+            Some(sign)
+        }
+        _ => None,
+    };
+    let constant = match tokens.get(*current_token) {
+        Some(Token {
+            kind: kind @ (TokenType::NumInt | TokenType::NumReal),
+            lexeme,
+            ..
+        }) => TreeNode::leaf(
+            NodeType::Factor,
+            kind.clone(),
+            lexeme.clone(),
+            span_at(tokens, *current_token),
+        ),
+        _ => return Err(expected(tokens, *current_token, "una constante numérica")),
+    };
+    *current_token += 1;
+    match sign {
+        Some(sign) => {
+            let mut node = TreeNode::new(NodeType::UnaryExpression);
+            node.span = sign.span.to(&constant.span);
+            node.children.push(sign);
+            node.children.push(constant);
+            Ok(node)
+        }
+        None => Ok(constant),
+    }
+}
+
+fn parse_default_statement(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::DefaultStatement);
+    match_token(tokens, TokenType::DEFAULT, current_token)?;
+    match_token(tokens, TokenType::COLON, current_token)?;
+    node.children.push(parse_statement_list(
+        tokens,
+        current_token,
+        diagnostics,
+        &[TokenType::CASE, TokenType::DEFAULT, TokenType::RBRACE],
+    ));
+    node.span = span_between(tokens, start, *current_token);
+    Ok(node)
+}
+
+fn parse_break_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
+    let mut node = TreeNode::new(NodeType::BreakStatement);
+    match_token(tokens, TokenType::BREAK, current_token)?;
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
+        node.span = span_between(tokens, start, *current_token);
+        Ok(node)
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ));
+        Err(expected(tokens, *current_token, "';'"))
     }
-    Ok(node)
 }
 
 fn parse_main_function(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::MainFunction);
     match_token(tokens, TokenType::MAIN, current_token)?;
     if let Err(err) = match_token(tokens, TokenType::LPAREN, current_token) {
-        log_error(err.to_string());
+        diagnostics.push(*err);
     }
     if let Err(err) = match_token(tokens, TokenType::RPAREN, current_token) {
-        log_error(err.to_string());
-    }
-    if let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
-        }
-    }
-    if let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+        diagnostics.push(*err);
     }
+    node.children
+        .push(parse_block(tokens, current_token, diagnostics));
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
-fn parse_write_statement(
-    tokens: &[(TokenType, String, usize, usize)],
-    current_token: &mut usize,
-) -> Result<TreeNode, String> {
+fn parse_write_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::WriteStatement);
     match_token(tokens, TokenType::WRITE, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ));
+        return Err(expected(tokens, *current_token, "un identificador"));
     }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ))
+        Err(expected(tokens, *current_token, "';'"))
     }
 }
 
-fn parse_read_statement(
-    tokens: &[(TokenType, String, usize, usize)],
-    current_token: &mut usize,
-) -> Result<TreeNode, String> {
+fn parse_read_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::ReadStatement);
     match_token(tokens, TokenType::READ, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ));
+        return Err(expected(tokens, *current_token, "un identificador"));
     }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ))
+        Err(expected(tokens, *current_token, "';'"))
     }
 }
 
 fn parse_return_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::ReturnStatement);
     match_token(tokens, TokenType::RETURN, current_token)?;
-    let expression_node = parse_expression(tokens, current_token)?;
+    let expression_node = parse_expression(tokens, current_token, diagnostics)?;
     node.children.push(expression_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ));
+        return Err(expected(tokens, *current_token, "';'"));
     }
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
-fn parse_cin_statement(
-    tokens: &[(TokenType, String, usize, usize)],
-    current_token: &mut usize,
-) -> Result<TreeNode, String> {
+fn parse_cin_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::CinStatement);
     match_token(tokens, TokenType::CIN, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ));
+        return Err(expected(tokens, *current_token, "un identificador"));
     }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ))
+        Err(expected(tokens, *current_token, "';'"))
     }
 }
 
 fn parse_cout_statement(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::CoutStatement);
     match_token(tokens, TokenType::COUT, current_token)?;
-    let expression_node = parse_expression(tokens, current_token)?;
+    let expression_node = parse_expression(tokens, current_token, diagnostics)?;
     node.children.push(expression_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+    if let Some(Token {
+        kind: TokenType::SEMICOLON,
+        ..
+    }) = tokens.get(*current_token)
+    {
         *current_token += 1;
     } else {
-        return Err(format!(
-            "Error de sintaxis: se esperaba ';' en la posición {:?}",
-            *current_token
-        ));
+        return Err(expected(tokens, *current_token, "';'"));
     }
+    node.span = span_between(tokens, start, *current_token);
     Ok(node)
 }
 
-fn parse_increment_statement(
-    tokens: &[(TokenType, String, usize, usize)],
-    current_token: &mut usize,
-) -> Result<TreeNode, String> {
+fn parse_increment_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::Increment);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 2;
-        if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+        if let Some(Token {
+            kind: TokenType::SEMICOLON,
+            ..
+        }) = tokens.get(*current_token)
+        {
             *current_token += 1;
         } else {
-            return Err(format!(
-                "Error de sintaxis: se esperaba ';' en la posición {:?}",
-                *current_token
-            ));
+            return Err(expected(tokens, *current_token, "';'"));
         }
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ))
+        Err(expected(tokens, *current_token, "un identificador"))
     }
 }
 
-fn parse_decrement_statement(
-    tokens: &[(TokenType, String, usize, usize)],
-    current_token: &mut usize,
-) -> Result<TreeNode, String> {
+fn parse_decrement_statement(tokens: &[Token], current_token: &mut usize) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::Decrement);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 2;
-        if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
+        if let Some(Token {
+            kind: TokenType::SEMICOLON,
+            ..
+        }) = tokens.get(*current_token)
+        {
             *current_token += 1;
         } else {
-            return Err(format!(
-                "Error de sintaxis: se esperaba ';' en la posición {:?}",
-                *current_token
-            ));
+            return Err(expected(tokens, *current_token, "';'"));
         }
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ))
+        Err(expected(tokens, *current_token, "un identificador"))
+    }
+}
+
+// Precedencia de los operadores binarios, de menor a mayor:
+// ||, &&, igualdad, relacionales, aditivos y multiplicativos.
+// Por encima quedan los operadores unarios y, finalmente, la potencia.
+fn binary_precedence(kind: &TokenType) -> Option<u8> {
+    match kind {
+        TokenType::OR => Some(1),
+        TokenType::AND => Some(2),
+        TokenType::EQ | TokenType::NEQ => Some(3),
+        TokenType::LT | TokenType::LTE | TokenType::GT | TokenType::GTE => Some(4),
+        TokenType::PLUS | TokenType::MINUS => Some(5),
+        TokenType::TIMES | TokenType::DIVIDE | TokenType::MODULO => Some(6),
+        _ => None,
     }
 }
 
 fn parse_expression(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    let mut node = parse_term(tokens, current_token)?;
-    while let Some((token, value, _, _)) = tokens.get(*current_token) {
-        match token {
-            TokenType::PLUS
-            | TokenType::MINUS
-            | TokenType::LT
-            | TokenType::LTE
-            | TokenType::GT
-            | TokenType::GTE
-            | TokenType::EQ
-            | TokenType::NEQ
-            | TokenType::AND
-            | TokenType::OR => {
-                *current_token += 1;
-                let term_node = parse_term(tokens, current_token)?;
-                let mut expression_node = TreeNode::new(NodeType::Expression);
-                expression_node.children.push(node);
-                expression_node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(token.clone()),
-                    value: Some(value.clone()),
-                    children: Vec::new(),
-                });
-                expression_node.children.push(term_node);
-                node = expression_node;
-            }
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    parse_binary_expression(tokens, current_token, diagnostics, 1)
+}
+
+// Precedence climbing: consume operadores cuya precedencia sea al menos `min_precedence`.
+// Todos estos niveles asocian a la izquierda.
+fn parse_binary_expression(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+    min_precedence: u8,
+) -> ParseResult<TreeNode> {
+    let mut node = parse_unary_expression(tokens, current_token, diagnostics)?;
+    while let Some(Token {
+        kind: token,
+        lexeme: value,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        let precedence = match binary_precedence(token) {
+            Some(precedence) if precedence >= min_precedence => precedence,
             _ => break,
-        }
+        };
+        let operator_span = span_at(tokens, *current_token);
+        *current_token += 1;
+        let right_node =
+            parse_binary_expression(tokens, current_token, diagnostics, precedence + 1)?;
+        // Los operadores multiplicativos forman términos
+        let node_type = if precedence >= 6 {
+            NodeType::Term
+        } else {
+            NodeType::Expression
+        };
+        let mut operation_node = TreeNode::new(node_type);
+        operation_node.span = node.span.to(&right_node.span);
+        operation_node.children.push(node);
+        operation_node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            token.clone(),
+            value.clone(),
+            operator_span,
+        ));
+        operation_node.children.push(right_node);
+        node = operation_node;
     }
     Ok(node)
 }
 
-fn parse_term(
-    tokens: &[(TokenType, String, usize, usize)],
+// -x, +x y !x. Ligan más fuerte que cualquier operador binario excepto la potencia,
+// por lo que -2 ^ 2 es -(2 ^ 2)
+fn parse_unary_expression(
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    let mut node = parse_factor(tokens, current_token)?;
-    while let Some((token, value, _, _)) = tokens.get(*current_token) {
-        match token {
-            TokenType::TIMES | TokenType::DIVIDE | TokenType::MODULO | TokenType::POWER => {
-                *current_token += 1;
-                let factor_node = parse_factor(tokens, current_token)?;
-                let mut term_node = TreeNode::new(NodeType::Term);
-                term_node.children.push(node);
-                term_node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(token.clone()),
-                    value: Some(value.clone()),
-                    children: Vec::new(),
-                });
-                term_node.children.push(factor_node);
-                node = term_node;
-            }
-            _ => break,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    match tokens.get(*current_token) {
+        Some(Token {
+            kind: token @ (TokenType::MINUS | TokenType::PLUS | TokenType::NOT),
+            lexeme: value,
+            ..
+        }) => {
+            let operator_span = span_at(tokens, *current_token);
+            *current_token += 1;
+            let operand_node = parse_unary_expression(tokens, current_token, diagnostics)?;
+            let mut node = TreeNode::new(NodeType::UnaryExpression);
+            node.span = operator_span.to(&operand_node.span);
+            node.children.push(TreeNode::leaf(
+                NodeType::Factor,
+                token.clone(),
+                value.clone(),
+                operator_span,
+            ));
+            node.children.push(operand_node);
+            Ok(node)
         }
+        _ => parse_power_expression(tokens, current_token, diagnostics),
+    }
+}
+
+// La potencia asocia a la derecha y su exponente puede llevar signo: 2 ^ -1
+fn parse_power_expression(
+    tokens: &[Token],
+    current_token: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let node = parse_factor(tokens, current_token, diagnostics)?;
+    match tokens.get(*current_token) {
+        Some(Token {
+            kind: TokenType::POWER,
+            lexeme: value,
+            ..
+        }) => {
+            let operator_span = span_at(tokens, *current_token);
+            *current_token += 1;
+            let exponent_node = parse_unary_expression(tokens, current_token, diagnostics)?;
+            let mut term_node = TreeNode::new(NodeType::Term);
+            term_node.span = node.span.to(&exponent_node.span);
+            term_node.children.push(node);
+            term_node.children.push(TreeNode::leaf(
+                NodeType::Factor,
+                TokenType::POWER,
+                value.clone(),
+                operator_span,
+            ));
+            term_node.children.push(exponent_node);
+            Ok(term_node)
+        }
+        _ => Ok(node),
     }
-    Ok(node)
 }
 
 fn parse_factor(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
-    if let Some((token, value, _, _)) = tokens.get(*current_token) {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    if let Some(Token {
+        kind: token,
+        lexeme: value,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        let start = *current_token;
         let mut node = TreeNode::new(NodeType::Factor);
+        node.span = span_at(tokens, start);
         match token {
-            TokenType::NumInt | TokenType::NumReal | TokenType::ID => {
+            TokenType::NumInt
+            | TokenType::NumReal
+            | TokenType::TRUE
+            | TokenType::FALSE
+            | TokenType::ID => {
                 node.token = Some(token.clone());
                 node.value = Some(value.clone());
                 *current_token += 1;
@@ -662,49 +1280,49 @@ fn parse_factor(
             }
             TokenType::LPAREN => {
                 *current_token += 1;
-                let expression_node = parse_expression(tokens, current_token)?;
+                let expression_node = parse_expression(tokens, current_token, diagnostics)?;
                 if let Err(err) = match_token(tokens, TokenType::RPAREN, current_token) {
-                    log_error(err.to_string());
+                    diagnostics.push(*err);
                 }
                 node.children.push(expression_node);
+                // Los paréntesis forman parte del factor
+                node.span = span_between(tokens, start, *current_token);
                 Ok(node)
             }
-            _ => Err(format!(
-                "Error de sintaxis: token inesperado {:?}",
-                tokens.get(*current_token)
-            )),
+            _ => Err(unexpected(tokens, *current_token)),
         }
     } else {
-        Err(format!(
-            "Error de sintaxis: token inesperado en la posición {:?}",
-            tokens.get(*current_token)
-        ))
+        Err(unexpected(tokens, *current_token))
     }
 }
 
 fn parse_assignment(
-    tokens: &[(TokenType, String, usize, usize)],
+    tokens: &[Token],
     current_token: &mut usize,
-) -> Result<TreeNode, String> {
+    diagnostics: &mut Diagnostics,
+) -> ParseResult<TreeNode> {
+    let start = *current_token;
     let mut node = TreeNode::new(NodeType::Assignment);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
+    if let Some(Token {
+        kind: TokenType::ID,
+        lexeme: id,
+        ..
+    }) = tokens.get(*current_token)
+    {
+        node.children.push(TreeNode::leaf(
+            NodeType::Factor,
+            TokenType::ID,
+            id.clone(),
+            span_at(tokens, *current_token),
+        ));
         *current_token += 1;
-        if let Err(err) = match_token(tokens, TokenType::ASSIGN, current_token) {
-            log_error(err.to_string());
-        }
-        let expression_node = parse_expression(tokens, current_token)?;
+        // Sin '=' la sentencia no tiene arreglo: el modo pánico la salta completa
+        match_token(tokens, TokenType::ASSIGN, current_token)?;
+        let expression_node = parse_expression(tokens, current_token, diagnostics)?;
         node.children.push(expression_node);
+        node.span = span_between(tokens, start, *current_token);
         Ok(node)
     } else {
-        Err(format!(
-            "Error de sintaxis: se esperaba un identificador en la posición {:?}",
-            tokens.get(*current_token)
-        ))
+        Err(expected(tokens, *current_token, "un identificador"))
     }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 const SIZE: usize = 211;
 const SHIFT: usize = 4;
@@ -14,21 +15,88 @@ pub struct BucketList {
     pub name: String,
     pub _type: String,
     pub value: String,
-    pub lines: Vec<LineList>, // Lista de líneas donde se usa el símbolo
-    pub memloc: usize,        // Ubicación en memoria
+    pub declaration_line: usize, // Línea donde se declaró
+    pub lines: Vec<LineList>,    // Lista de líneas donde se usa el símbolo
+    pub memloc: usize,           // Ubicación en memoria
+    pub scope: usize,            // Ámbito donde se declaró
+}
+
+// Fila de la tabla de símbolos tal como se muestra y exporta en el IDE
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SymbolRecord {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub value: String,
+    pub memloc: usize,
+    pub declaration_line: usize,
+    pub use_lines: Vec<usize>,
+    pub scope: usize,
+    pub scope_kind: ScopeKind,
+}
+
+// Encabezado del CSV, en el mismo orden que los campos de SymbolRecord
+const CSV_HEADER: &str = "name,type,value,memloc,declaration_line,use_lines,scope,scope_kind";
+
+// Entrecomilla un campo de CSV si contiene separadores o comillas
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Clase de ámbito que abre un bloque de código
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ScopeKind {
+    Global,
+    Function,
+    Block,
+    // El for, que contiene lo declarado en su inicialización
+    For,
+}
+
+// Un ámbito es una tabla hash con encadenamiento: cada posición guarda la
+// lista de símbolos cuyo nombre cae en ella
+struct Scope {
+    kind: ScopeKind,
+    parent: Option<usize>,
+    table: Vec<Vec<BucketList>>,
+}
+
+impl Scope {
+    fn new(kind: ScopeKind, parent: Option<usize>) -> Self {
+        Scope {
+            kind,
+            parent,
+            table: vec![Vec::new(); SIZE],
+        }
+    }
 }
 
 // Implementación de la tabla de símbolos
 pub struct SymbolTable {
-    table: Vec<Option<BucketList>>, // Cambiado a Vec para una mejor gestión
-    next_loc: usize,                // Siguiente ubicación de memoria
+    scopes: Vec<Scope>, // Todos los ámbitos, incluso los ya cerrados
+    current: usize,     // Ámbito abierto más interno
+    next_loc: usize,    // Siguiente ubicación de memoria
+    // Ubicación de cada declarador, por el byte donde empieza en el código
+    declarations: HashMap<usize, usize>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         SymbolTable {
-            table: vec![None; SIZE], // Inicializa la tabla con None
-            next_loc: 0,             // Inicializa la ubicación en 0
+            scopes: vec![Scope::new(ScopeKind::Global, None)], // Inicia con el ámbito global
+            current: 0,
+            next_loc: 0, // Inicializa la ubicación en 0
+            declarations: HashMap::new(),
         }
     }
 
@@ -41,51 +109,171 @@ impl SymbolTable {
         temp
     }
 
-    // Inserta un símbolo en la tabla
-    pub fn insert(&mut self, name: &str, _type: &str, value: &str, lineno: usize, loc: usize) {
-        let h = self.hash(name);
-        let bucket = &mut self.table[h];
-
-        // Si el símbolo no está en la tabla
-        if bucket.is_none() {
-            let new_bucket = BucketList {
-                name: name.to_string(),
-                _type: _type.to_string(),
-                value: value.to_string(),
-                lines: vec![LineList { lineno }], // Inicializa con la línea
-                memloc: loc,
-            };
-            *bucket = Some(new_bucket);
-        } else {
-            let current = bucket.as_mut().unwrap();
-            // Verifica si el símbolo ya existe
-            if current.name == name {
-                // Comprueba si la línea ya está registrada
-                if !current.lines.iter().any(|line| line.lineno == lineno) {
-                    current.lines.push(LineList { lineno });
-                }
-            } else {
-                // Manejo de colisiones simple, añadiendo nuevos buckets en el mismo índice
-                // Aquí puedes implementar un mejor manejo de colisiones si lo deseas
-                // Por simplicidad, solo agregamos la línea al bucket existente
-                current.lines.push(LineList { lineno });
-            }
+    // Abre un ámbito anidado dentro del actual
+    pub fn enter_scope(&mut self, kind: ScopeKind) {
+        self.scopes.push(Scope::new(kind, Some(self.current)));
+        self.current = self.scopes.len() - 1;
+    }
+
+    // Cierra el ámbito actual; sus símbolos se conservan para consultarlos después
+    pub fn exit_scope(&mut self) {
+        if let Some(parent) = self.scopes[self.current].parent {
+            self.current = parent;
         }
     }
 
-    // Busca la ubicación de memoria de un símbolo
-    pub fn lookup(&self, name: &str) -> Option<usize> {
+    // Ámbito abierto más interno
+    pub fn current_scope(&self) -> usize {
+        self.current
+    }
+
+    // Clase del ámbito indicado
+    pub fn scope_kind(&self, scope: usize) -> ScopeKind {
+        self.scopes[scope].kind
+    }
+
+    // Posición (ámbito, cadena, índice) del símbolo, buscando solo en `scope`
+    fn find_in(&self, scope: usize, name: &str) -> Option<(usize, usize, usize)> {
         let h = self.hash(name);
-        let current = &self.table[h];
+        self.scopes[scope].table[h]
+            .iter()
+            .position(|bucket| bucket.name == name)
+            .map(|index| (scope, h, index))
+    }
 
-        if let Some(bucket) = current {
-            if bucket.name == name {
-                return Some(bucket.memloc);
+    // Posición del símbolo buscando del ámbito actual hacia afuera
+    fn find(&self, name: &str) -> Option<(usize, usize, usize)> {
+        let mut scope = Some(self.current);
+        while let Some(id) = scope {
+            if let Some(position) = self.find_in(id, name) {
+                return Some(position);
             }
+            scope = self.scopes[id].parent;
         }
         None
     }
 
+    fn bucket(&self, (scope, h, index): (usize, usize, usize)) -> &BucketList {
+        &self.scopes[scope].table[h][index]
+    }
+
+    // Inserta un símbolo en el ámbito actual; si ya existe en él solo agrega la línea
+    pub fn insert(&mut self, name: &str, _type: &str, value: &str, lineno: usize, loc: usize) {
+        match self.find_in(self.current, name) {
+            Some(position) => self.add_line_at(position, lineno),
+            None => {
+                let h = self.hash(name);
+                let scope = self.current;
+                self.scopes[scope].table[h].push(BucketList {
+                    name: name.to_string(),
+                    _type: _type.to_string(),
+                    value: value.to_string(),
+                    declaration_line: lineno,
+                    lines: Vec::new(),
+                    memloc: loc,
+                    scope,
+                });
+            }
+        }
+    }
+
+    fn add_line_at(&mut self, (scope, h, index): (usize, usize, usize), lineno: usize) {
+        let bucket = &mut self.scopes[scope].table[h][index];
+        // Comprueba si la línea ya está registrada como uso; un uso en la línea de
+        // la declaración sí se registra
+        if !bucket.lines.iter().any(|line| line.lineno == lineno) {
+            bucket.lines.push(LineList { lineno });
+        }
+    }
+
+    // Agrega una línea de uso al símbolo visible con ese nombre
+    pub fn add_line(&mut self, name: &str, lineno: usize) {
+        if let Some(position) = self.find(name) {
+            self.add_line_at(position, lineno);
+        }
+    }
+
+    // Cambia el valor mostrado del símbolo visible con ese nombre
+    pub fn set_value(&mut self, name: &str, value: &str) {
+        if let Some((scope, h, index)) = self.find(name) {
+            self.scopes[scope].table[h][index].value = value.to_string();
+        }
+    }
+
+    // Busca el símbolo visible desde el ámbito actual, del más interno al global
+    pub fn lookup(&self, name: &str) -> Option<&BucketList> {
+        self.find(name).map(|position| self.bucket(position))
+    }
+
+    // Busca el símbolo solo en el ámbito actual
+    pub fn lookup_current(&self, name: &str) -> Option<&BucketList> {
+        self.find_in(self.current, name)
+            .map(|position| self.bucket(position))
+    }
+
+    // Entradas de todos los ámbitos en orden de ubicación de memoria
+    pub fn symbols(&self) -> Vec<BucketList> {
+        let mut symbols: Vec<BucketList> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.table.iter().flatten())
+            .cloned()
+            .collect();
+        symbols.sort_by_key(|bucket| bucket.memloc);
+        symbols
+    }
+
+    // Recorre todas las entradas como filas exportables, en orden de ubicación de memoria
+    pub fn entries(&self) -> impl Iterator<Item = SymbolRecord> + '_ {
+        self.symbols().into_iter().map(|bucket| SymbolRecord {
+            declaration_line: bucket.declaration_line,
+            use_lines: bucket.lines.iter().map(|line| line.lineno).collect(),
+            scope_kind: self.scope_kind(bucket.scope),
+            name: bucket.name,
+            _type: bucket._type,
+            value: bucket.value,
+            memloc: bucket.memloc,
+            scope: bucket.scope,
+        })
+    }
+
+    // Exporta la tabla como CSV; las líneas de uso van separadas por espacios
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for record in self.entries() {
+            let use_lines: Vec<String> = record.use_lines.iter().map(|l| l.to_string()).collect();
+            let row = [
+                csv_field(&record.name),
+                csv_field(&record._type),
+                csv_field(&record.value),
+                record.memloc.to_string(),
+                record.declaration_line.to_string(),
+                use_lines.join(" "),
+                record.scope.to_string(),
+                format!("{:?}", record.scope_kind),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // Exporta la tabla como un arreglo JSON de filas
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.entries().collect::<Vec<_>>())
+    }
+
+    // Asocia el declarador que empieza en `start_byte` con la ubicación de su variable
+    pub fn bind_declaration(&mut self, start_byte: usize, loc: usize) {
+        self.declarations.insert(start_byte, loc);
+    }
+
+    // Ubicación de la variable creada por el declarador que empieza en `start_byte`
+    pub fn declaration_location(&self, start_byte: usize) -> Option<usize> {
+        self.declarations.get(&start_byte).copied()
+    }
+
     // Obtiene la siguiente ubicación de memoria y la incrementa
     pub fn next_location(&mut self) -> usize {
         let loc = self.next_loc;
@@ -95,19 +283,18 @@ impl SymbolTable {
 
     // Imprime la tabla de símbolos
     pub fn print(&self) {
-        println!("Variable Name     Type    Value   Location    Line Numbers");
-        println!("--------------    ------  ------  --------    -----------");
-        for bucket in &self.table {
-            if let Some(bucket) = bucket {
-                print!(
-                    "{:<15} {:<15} {:<15} {:<10} ",
-                    bucket.name, bucket._type, bucket.value, bucket.memloc
-                );
-                for line in &bucket.lines {
-                    print!("{} ", line.lineno);
-                }
-                println!();
+        println!("Variable Name     Type    Value   Location    Scope   Line Numbers");
+        println!("--------------    ------  ------  --------    -----   -----------");
+        for bucket in self.symbols() {
+            print!(
+                "{:<15} {:<15} {:<15} {:<10} {:<7} ",
+                bucket.name, bucket._type, bucket.value, bucket.memloc, bucket.scope
+            );
+            print!("{} ", bucket.declaration_line);
+            for line in &bucket.lines {
+                print!("{} ", line.lineno);
             }
+            println!();
         }
     }
 }
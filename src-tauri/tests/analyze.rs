@@ -0,0 +1,445 @@
+mod common;
+
+use app::analyze::analyze;
+use app::globals::{codes, Diagnostic, Diagnostics, ExpType, NodeType, Severity, TreeNode, Value};
+use app::symTab::{ScopeKind, SymbolTable};
+use common::parse;
+
+// Análisis de un programa sin errores de sintaxis, con todos sus diagnósticos
+fn analyze_source(source: &str) -> (TreeNode, SymbolTable, Vec<Diagnostic>) {
+    let mut tree = parse(source);
+    let mut diagnostics = Diagnostics::new();
+    let symbol_table = analyze(&mut tree, &mut diagnostics);
+    (tree, symbol_table, diagnostics.into_vec())
+}
+
+// Igual que `analyze_source` pero solo con los errores, sin advertencias
+fn check(source: &str) -> (TreeNode, SymbolTable, Vec<Diagnostic>) {
+    let (tree, symbol_table, diagnostics) = analyze_source(source);
+    let errors = diagnostics
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    (tree, symbol_table, errors)
+}
+
+// Valor constante anotado en cada uso de la variable, en orden de aparición
+fn constants_at_uses(t: &TreeNode, name: &str, found: &mut Vec<Option<Value>>) {
+    if t.node_type == NodeType::Factor && t.value.as_deref() == Some(name) {
+        found.push(t.constant);
+    }
+    for child in &t.children {
+        constants_at_uses(child, name, found);
+    }
+}
+
+#[test]
+fn declarations_and_uses_fill_the_symbol_table() {
+    let (_, symbol_table, errors) = check("main() {\n  int a, b;\n  double c;\n  a = b + 1;\n}");
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let symbols = symbol_table.symbols();
+    let rows: Vec<(&str, &str, usize)> = symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s._type.as_str(), s.memloc))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![("a", "int", 0), ("b", "int", 1), ("c", "double", 2)]
+    );
+    let lines: Vec<usize> = symbols[0].lines.iter().map(|l| l.lineno).collect();
+    assert_eq!((symbols[0].declaration_line, lines), (2, vec![4]));
+}
+
+#[test]
+fn a_use_on_the_declaration_line_is_a_use_line() {
+    let (_, symbol_table, errors) =
+        check("main() {\n  int a = 1; a = a + 1;\n  cout a; cout a;\n}");
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let entry = symbol_table.entries().next().unwrap();
+    assert_eq!(entry.declaration_line, 2);
+    assert_eq!(entry.use_lines, vec![2, 3]);
+}
+
+#[test]
+fn expressions_are_annotated_with_their_type() {
+    let (tree, _, errors) = check("main() { int i; double d; d = i * 2 + d; }");
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let assignment = &tree.children[0].children[0].children[2];
+    let sum = &assignment.children[1];
+    assert_eq!(sum.exp_type, ExpType::Double);
+    assert_eq!(sum.children[0].exp_type, ExpType::Integer);
+}
+
+#[test]
+fn conditions_must_be_boolean_or_int() {
+    let (_, _, errors) = check(
+        "main() { int x; double d; cin d; if d + 1 { x = 0; } while x < 3 && true { x++; } if x { x = 1; } }",
+    );
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::NON_BOOLEAN_CONDITION);
+    assert_eq!(errors[0].primary_span.col_start, 37);
+}
+
+#[test]
+fn logical_operators_need_boolean_operands() {
+    let (_, _, errors) = check("main() { int x; x = 1; if x && true { x = 2; } }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::TYPE_MISMATCH);
+}
+
+#[test]
+fn switch_labels_are_unique_integers() {
+    let (_, _, errors) =
+        check("main() { int x; switch x { case 1: x = 0; case 2.5: x = 1; case 1: x = 2; } }");
+
+    let found: Vec<&str> = errors.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(
+        found,
+        vec![codes::NON_INTEGER_SWITCH, codes::DUPLICATE_CASE_LABEL]
+    );
+}
+
+#[test]
+fn signed_case_labels_are_compared_by_value() {
+    let (_, _, errors) = check(
+        "main() { int x; cin x; switch x { case -1: x = 0; case 1: x = 1; case +1: x = 2; } }",
+    );
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::DUPLICATE_CASE_LABEL);
+    assert_eq!(errors[0].primary_span.col_start, 71);
+}
+
+#[test]
+fn out_of_range_case_label_is_not_compared_with_the_others() {
+    let (_, _, errors) = check(
+        "main() { int x; cin x; switch x { case 99999999999999999999: x = 1; case 0: x = 2; } }",
+    );
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::NUMBER_OUT_OF_RANGE);
+    assert_eq!(errors[0].primary_span.col_start, 40);
+}
+
+#[test]
+fn block_declarations_shadow_outer_variables() {
+    let (tree, symbol_table, errors) =
+        check("main() {\n  int x;\n  if true {\n    double x;\n    x = 1.5;\n  }\n  x = 2;\n}");
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let symbols = symbol_table.symbols();
+    assert_eq!(symbols.len(), 2);
+    let uses: Vec<(usize, Vec<usize>)> = symbols
+        .iter()
+        .map(|s| {
+            (
+                s.declaration_line,
+                s.lines.iter().map(|l| l.lineno).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(uses, vec![(2, vec![7]), (4, vec![5])]);
+
+    let if_block = &tree.children[0].children[0].children[1].children[1];
+    assert_eq!(if_block.children[1].children[0].exp_type, ExpType::Double);
+}
+
+#[test]
+fn for_init_declarations_only_exist_inside_the_loop() {
+    let (_, symbol_table, errors) = check(
+        "main() {\n  int i = 5;\n  for (int i = 0; i < 3; i++) {\n    cout i;\n  }\n  cout i;\n}",
+    );
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let scopes: Vec<(usize, ScopeKind)> = symbol_table
+        .entries()
+        .map(|entry| (entry.declaration_line, entry.scope_kind))
+        .collect();
+    assert_eq!(scopes, vec![(2, ScopeKind::Block), (3, ScopeKind::For)]);
+
+    let (_, _, errors) = check("main() {\n  for (int k = 0; k < 2; k++) { }\n  cout k;\n}");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::UNDECLARED_VARIABLE);
+    assert_eq!(errors[0].primary_span.line, 3);
+    assert_eq!(errors[0].labels[0].span.line, 2);
+}
+
+#[test]
+fn integers_widen_to_double_but_doubles_do_not_narrow() {
+    let (tree, _, errors) = check("main() {\n  int i;\n  double d;\n  d = i + 1;\n  i = d * 2;\n}");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::INCOMPATIBLE_ASSIGNMENT);
+    assert_eq!(errors[0].primary_span.line, 5);
+    assert_eq!(errors[0].labels[0].span.col_start, 3);
+    assert!(errors[0].help.is_some());
+
+    let widened = &tree.children[0].children[0].children[2];
+    assert_eq!(widened.exp_type, ExpType::Double);
+    assert_eq!(widened.children[1].exp_type, ExpType::Integer);
+}
+
+#[test]
+fn booleans_cannot_be_stored_in_numeric_variables() {
+    let (_, _, errors) = check("main() { double d; d = 1 < 2; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::INCOMPATIBLE_ASSIGNMENT);
+}
+
+#[test]
+fn modulo_requires_integer_operands() {
+    let (_, _, errors) = check("main() { int i; double d; i = i % 2; d = d % 2; d = 7 % 2.0; }");
+
+    let found: Vec<&str> = errors.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(found, vec![codes::DOUBLE_MODULO, codes::DOUBLE_MODULO]);
+}
+
+#[test]
+fn undeclared_variables_are_errors_at_each_use() {
+    let (_, _, errors) = check("main() {\n  int x;\n  x = y + 1;\n  y = 2;\n}");
+
+    let found: Vec<(&str, usize)> = errors
+        .iter()
+        .map(|d| (d.code.as_str(), d.primary_span.line))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::UNDECLARED_VARIABLE, 3),
+            (codes::UNDECLARED_VARIABLE, 4)
+        ]
+    );
+    assert!(errors[0].help.is_some());
+}
+
+#[test]
+fn block_local_variable_used_outside_points_at_its_declaration() {
+    let (_, _, errors) = check("main() {\n  if true {\n    int t;\n    t = 1;\n  }\n  t = 2;\n}");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::UNDECLARED_VARIABLE);
+    assert_eq!(errors[0].primary_span.line, 6);
+    assert_eq!(errors[0].labels[0].span.line, 3);
+}
+
+#[test]
+fn redeclaration_in_the_same_scope_points_at_the_original() {
+    let (_, symbol_table, errors) = check("main() {\n  int x;\n  double x;\n  x = 1;\n}");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::REDECLARED_VARIABLE);
+    assert_eq!(errors[0].primary_span.line, 3);
+    assert_eq!(errors[0].labels[0].span.line, 2);
+    // La declaración original se conserva
+    assert_eq!(symbol_table.symbols().len(), 1);
+    assert_eq!(symbol_table.symbols()[0]._type, "int");
+}
+
+#[test]
+fn variables_that_are_only_written_are_unused() {
+    let (_, _, diagnostics) =
+        analyze_source("main() {\n  int a, b, c, d;\n  a = 1;\n  cin b;\n  d = c;\n  cout d;\n}");
+
+    let warnings: Vec<(&str, usize, usize)> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.code.as_str(),
+                d.primary_span.line,
+                d.primary_span.col_start,
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (codes::UNUSED_VARIABLE, 2, 7),
+            (codes::UNUSED_VARIABLE, 2, 10)
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+}
+
+#[test]
+fn initializers_fill_the_value_column_and_see_earlier_declarators() {
+    let (_, symbol_table, errors) =
+        check("main() {\n  int x = 5, y = x + 1;\n  double z;\n  cout y;\n}");
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let values: Vec<(String, String)> = symbol_table
+        .symbols()
+        .into_iter()
+        .map(|s| (s.name, s.value))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            ("x".to_string(), "5".to_string()),
+            ("y".to_string(), "6".to_string()),
+            ("z".to_string(), "0".to_string())
+        ]
+    );
+}
+
+#[test]
+fn initializers_are_type_checked_like_assignments() {
+    let (_, _, errors) = check("main() { int i = 2.5; double d = 1; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::INCOMPATIBLE_ASSIGNMENT);
+    assert_eq!(errors[0].primary_span.col_start, 18);
+}
+
+#[test]
+fn initialized_but_never_read_is_still_unused() {
+    let (_, _, diagnostics) = analyze_source("main() { int a = 1, b = a; }");
+
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].code, codes::UNUSED_VARIABLE);
+    assert_eq!(diagnostics[0].primary_span.col_start, 21);
+}
+
+#[test]
+fn constant_initializers_are_folded_with_int_and_double_arithmetic() {
+    let (tree, symbol_table, errors) = check(
+        "main() {\n  double d = 2 ^ 3 + 7 / 2 * 1.5;\n  int i = 7 % 3 - (-2), n;\n  cin n;\n  int m = n + i;\n  cout d + m;\n}",
+    );
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let values: Vec<String> = symbol_table
+        .symbols()
+        .into_iter()
+        .map(|s| s.value)
+        .collect();
+    assert_eq!(values, vec!["12.5", "3", "0", "n + i"]);
+    let initializer = &tree.children[0].children[0].children[0].children[0].children[0];
+    assert_eq!(initializer.constant, Some(Value::Double(12.5)));
+}
+
+#[test]
+fn known_values_are_forgotten_inside_loops_and_branches() {
+    let (tree, _, errors) = check(
+        "main() {\n  int n, x = 1;\n  cin n;\n  x = x + 1;\n  if n > x { x = 7; }\n  cout x;\n  x = 4;\n  while n > x { n = n - x; x = 5; }\n  cout x + n;\n}",
+    );
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let mut found = Vec::new();
+    constants_at_uses(&tree, "x", &mut found);
+    // Las variables que reciben un valor no se anotan
+    assert_eq!(
+        found,
+        vec![
+            None,
+            Some(Value::Int(1)),
+            Some(Value::Int(2)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        ]
+    );
+}
+
+#[test]
+fn division_by_a_constant_zero_is_an_error() {
+    let (_, _, errors) = check(
+        "main() {\n  int a, b;\n  cin a;\n  b = a / (1 - 1);\n  b = a % 0;\n  double d = 1.0 / 2;\n  cout b + d;\n}",
+    );
+
+    let found: Vec<(&str, usize, usize)> = errors
+        .iter()
+        .map(|d| {
+            (
+                d.code.as_str(),
+                d.primary_span.line,
+                d.labels[0].span.col_start,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::DIVISION_BY_ZERO, 4, 11),
+            (codes::DIVISION_BY_ZERO, 5, 11)
+        ]
+    );
+}
+
+#[test]
+fn constant_operations_that_would_fail_at_run_time_are_errors() {
+    let (_, _, errors) = check(
+        "main() {\n  int a = 0 ^ -1;\n  int b = 3037000500 * 3037000500 + 1;\n  cout a + b;\n}",
+    );
+
+    let found: Vec<(&str, usize, usize)> = errors
+        .iter()
+        .map(|d| {
+            (
+                d.code.as_str(),
+                d.primary_span.line,
+                d.primary_span.col_start,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::DIVISION_BY_ZERO, 2, 13),
+            (codes::CONSTANT_OVERFLOW, 3, 22)
+        ]
+    );
+}
+
+#[test]
+fn integer_literals_out_of_range_are_errors() {
+    let (_, _, errors) = check(
+        "main() {\n  int x;\n  x = 99999999999999999999;\n  x = 9223372036854775807;\n  x = -9223372036854775808;\n  cout x;\n}",
+    );
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::NUMBER_OUT_OF_RANGE);
+    assert_eq!(errors[0].primary_span.line, 3);
+    assert_eq!(errors[0].primary_span.col_start, 7);
+    assert!(errors[0].help.is_some());
+}
+
+#[test]
+fn a_second_default_is_a_duplicate_label() {
+    let (_, _, errors) =
+        check("main() { int x; switch x { default: x = 0; case 1: x = 1; default: x = 2; } }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::DUPLICATE_CASE_LABEL);
+    assert_eq!(errors[0].primary_span.col_start, 59);
+}
+
+#[test]
+fn break_is_only_allowed_inside_loops_and_switch() {
+    let (_, _, errors) = check(
+        "main() { int x; cin x; while x > 0 { if x == 3 { break; } x--; } \
+         switch x { case 0: break; } for (;;) { break; } }",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let (_, _, errors) = check("main() { int x; cin x; if x > 0 { break; } break; cout 1; }");
+    let found: Vec<(&str, usize)> = errors
+        .iter()
+        .map(|d| (d.code.as_str(), d.primary_span.col_start))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::BREAK_OUTSIDE_LOOP, 35),
+            (codes::BREAK_OUTSIDE_LOOP, 44)
+        ]
+    );
+}
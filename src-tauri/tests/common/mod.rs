@@ -0,0 +1,26 @@
+// Cada archivo de pruebas usa solo algunas de estas funciones
+#![allow(dead_code)]
+
+use app::analyze::analyze;
+use app::globals::{Diagnostics, TreeNode};
+use app::lexer::get_token;
+use app::parser::parse_program;
+use app::symTab::SymbolTable;
+
+// Árbol sintáctico de un programa sin errores léxicos ni sintácticos
+pub fn parse(source: &str) -> TreeNode {
+    let mut diagnostics = Diagnostics::new();
+    let tokens = get_token(source, &mut diagnostics);
+    let tree = parse_program(&tokens, &mut 0, &mut diagnostics).unwrap();
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics.diagnostics());
+    tree
+}
+
+// Árbol analizado y tabla de símbolos de un programa que compila sin errores
+pub fn compile(source: &str) -> (TreeNode, SymbolTable) {
+    let mut tree = parse(source);
+    let mut diagnostics = Diagnostics::new();
+    let symbol_table = analyze(&mut tree, &mut diagnostics);
+    assert!(!diagnostics.has_errors(), "{:?}", diagnostics.diagnostics());
+    (tree, symbol_table)
+}
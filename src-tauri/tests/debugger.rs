@@ -0,0 +1,159 @@
+mod common;
+
+use app::debugger::{DebugCommand, DebugEvent, Debugger, PauseReason, PausedState};
+use app::globals::Value;
+use app::interpreter::{BufferedConsole, Interpreter};
+use common::compile;
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const PROGRAM: &str =
+    "main() {\n  int x = 1;\n  if x > 0 {\n    x = x + 1;\n    x = x * 10;\n  }\n  cout x;\n}";
+
+struct Session {
+    commands: Sender<DebugCommand>,
+    events: Receiver<DebugEvent>,
+    program: JoinHandle<String>,
+}
+
+impl Session {
+    // Siguiente pausa, saltando los avisos de reanudación
+    fn next_pause(&self) -> PausedState {
+        loop {
+            match self.events.recv_timeout(Duration::from_secs(5)) {
+                Ok(DebugEvent::Paused(paused)) => return paused,
+                Ok(DebugEvent::Resumed) => continue,
+                Err(err) => panic!("el programa no se detuvo: {:?}", err),
+            }
+        }
+    }
+
+    fn send(&self, command: DebugCommand) -> PausedState {
+        self.commands.send(command).unwrap();
+        self.next_pause()
+    }
+
+    // Reanuda hasta el final y devuelve la salida del programa
+    fn finish(self, command: DebugCommand) -> String {
+        self.commands.send(command).unwrap();
+        self.program.join().unwrap()
+    }
+}
+
+fn debug(source: &str, breakpoints: &[usize], stop_on_entry: bool) -> Session {
+    let (tree, symbol_table) = compile(source);
+
+    let breakpoints = Arc::new(Mutex::new(
+        breakpoints.iter().copied().collect::<HashSet<_>>(),
+    ));
+    let (commands, command_receiver) = mpsc::channel();
+    let (event_sender, events) = mpsc::channel();
+    let program = thread::spawn(move || {
+        let mut debugger = Debugger::new(
+            symbol_table,
+            breakpoints,
+            command_receiver,
+            stop_on_entry,
+            move |event| event_sender.send(event).unwrap(),
+        );
+        let mut console = BufferedConsole::new("");
+        let result = Interpreter::new(&mut console)
+            .with_observer(&mut debugger)
+            .run(&tree);
+        assert!(result.is_ok(), "{:?}", result.err());
+        console.output
+    });
+    Session {
+        commands,
+        events,
+        program,
+    }
+}
+
+#[test]
+fn step_over_skips_the_body_and_shows_variables_with_memloc() {
+    let session = debug(PROGRAM, &[], true);
+
+    let entry = session.next_pause();
+    assert_eq!(entry.reason, PauseReason::Entry);
+    assert_eq!(entry.span.line, 2);
+    assert!(entry.variables.is_empty());
+
+    let at_if = session.send(DebugCommand::StepOver);
+    assert_eq!((at_if.reason, at_if.span.line), (PauseReason::Step, 3));
+    let x = &at_if.variables[0];
+    assert_eq!((x.name.as_str(), x._type.as_str()), ("x", "int"));
+    assert_eq!(
+        (x.value, x.memloc, x.scope_depth),
+        (Value::Int(1), Some(0), 1)
+    );
+
+    let at_cout = session.send(DebugCommand::StepOver);
+    assert_eq!(at_cout.span.line, 7);
+    assert_eq!(at_cout.variables[0].value, Value::Int(20));
+
+    assert_eq!(session.finish(DebugCommand::StepOver), "20\n");
+}
+
+#[test]
+fn step_into_enters_the_body_and_step_out_leaves_it() {
+    let session = debug(PROGRAM, &[3], false);
+
+    assert_eq!(session.next_pause().reason, PauseReason::Breakpoint);
+    assert_eq!(session.send(DebugCommand::StepInto).span.line, 4);
+    assert_eq!(session.send(DebugCommand::StepOut).span.line, 7);
+    assert_eq!(session.finish(DebugCommand::Continue), "20\n");
+}
+
+#[test]
+fn breakpoints_stop_on_every_loop_iteration() {
+    let session = debug(
+        "main() {\n  int i = 0;\n  while i < 3 {\n    i++;\n  }\n  cout i;\n}",
+        &[4],
+        false,
+    );
+
+    for expected in 0..3 {
+        let paused = session.next_pause();
+        assert_eq!(
+            (paused.reason, paused.span.line),
+            (PauseReason::Breakpoint, 4)
+        );
+        assert_eq!(paused.variables[0].value, Value::Int(expected));
+        session.commands.send(DebugCommand::Continue).unwrap();
+    }
+    assert_eq!(session.program.join().unwrap(), "3\n");
+}
+
+#[test]
+fn a_breakpoint_on_a_one_line_loop_stops_on_every_pass() {
+    let session = debug(
+        "main() {\n  int i = 0;\n  while i < 3 { i++; }\n  cout i;\n}",
+        &[3],
+        false,
+    );
+
+    assert_eq!(session.next_pause().variables[0].value, Value::Int(0));
+    // Cada vuelta vuelve a evaluar la condición y se detiene otra vez
+    for expected in 1..=3 {
+        let paused = session.send(DebugCommand::Continue);
+        assert_eq!(
+            (paused.reason, paused.span.line),
+            (PauseReason::Breakpoint, 3)
+        );
+        assert_eq!(paused.variables[0].value, Value::Int(expected));
+    }
+    assert_eq!(session.finish(DebugCommand::Continue), "3\n");
+}
+
+#[test]
+fn closing_the_session_lets_the_program_finish() {
+    let session = debug(PROGRAM, &[4, 5], false);
+
+    assert_eq!(session.next_pause().span.line, 4);
+    drop(session.commands);
+    assert_eq!(session.program.join().unwrap(), "20\n");
+}
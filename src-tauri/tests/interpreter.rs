@@ -0,0 +1,312 @@
+mod common;
+
+use app::globals::{codes, TreeNode, Value};
+use app::interpreter::{
+    run, BufferedConsole, ConsoleEvent, Interpreter, Limits, RunResult, StreamConsole,
+};
+use common::compile;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+fn run_source(source: &str, input: &str) -> RunResult {
+    run(&compile(source).0, input, Limits::default())
+}
+
+const INFINITE_LOOP: &str = "main() {\n  int x = 0;\n  while true {\n    x++;\n  }\n}";
+
+#[test]
+fn arithmetic_keeps_int_and_double_apart() {
+    let result = run_source(
+        "main() {\n  int i = 7 / 2;\n  double d = 7 / 2.0;\n  d = d + i;\n  cout i;\n  cout d;\n  cout 2 ^ 10 % 1000;\n}",
+        "",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "3\n6.5\n24\n");
+    assert_eq!(result.exit_value, Some(Value::Int(0)));
+}
+
+#[test]
+fn large_ints_compare_exactly() {
+    let result = run_source(
+        "main() {\n  int a, b;\n  cin a;\n  b = 9007199254740992;\n  cout a == b;\n  cout a > b;\n  cout 9007199254740993 != b;\n}",
+        "9007199254740993",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "false\ntrue\ntrue\n");
+}
+
+#[test]
+fn the_smallest_int_can_be_written_as_a_literal() {
+    let result = run_source(
+        "main() {\n  int x = -9223372036854775808;\n  cout x;\n}",
+        "",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "-9223372036854775808\n");
+}
+
+#[test]
+fn every_loop_kind_runs_its_body() {
+    let result = run_source(
+        "main() {\n  int i, n = 0;\n  while n < 3 { n++; }\n  do { n = n * 2; } while n < 20;\n  repeat { n--; } until n == 20;\n  for (i = 0; i < 3; i++) { n = n + i; }\n  cout n;\n}",
+        "",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "23\n");
+}
+
+#[test]
+fn for_init_declarations_run_in_their_own_scope() {
+    let result = run_source(
+        "main() {\n  int i = 10, n = 0;\n  for (int i = 0; i < 3; i++) { n = n + i; }\n  for (double d = 0.5; d < 2; d = d + 1) { cout d; }\n  cout i;\n  cout n;\n}",
+        "",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "0.5\n1.5\n10\n3\n");
+}
+
+#[test]
+fn bare_blocks_shadow_outer_variables() {
+    let result = run_source(
+        "main() {\n  int x = 1;\n  {\n    double x = 2.5;\n    cout x;\n  }\n  cout x;\n}",
+        "",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "2.5\n1\n");
+}
+
+#[test]
+fn switch_falls_through_until_break() {
+    let source = "main() {\n  int x;\n  cin x;\n  switch x {\n    case 1: cout 10;\n    case 2: cout 20; break;\n    default: cout 0;\n  }\n}";
+
+    assert_eq!(run_source(source, "1").output, "10\n20\n");
+    assert_eq!(run_source(source, "2").output, "20\n");
+    assert_eq!(run_source(source, "5").output, "0\n");
+}
+
+#[test]
+fn negative_case_labels_match_negative_selectors() {
+    let source = "main() {\n  int x;\n  cin x;\n  switch x {\n    case -1: cout 10; break;\n    case 1: cout 20;\n  }\n}";
+
+    assert_eq!(run_source(source, "-1").output, "10\n");
+    assert_eq!(run_source(source, "1").output, "20\n");
+}
+
+#[test]
+fn break_leaves_only_the_innermost_loop() {
+    let result = run_source(
+        "main() {\n  int i, j, n = 0;\n  for (i = 0; i < 3; i++) {\n    j = 0;\n    while true {\n      j++;\n      if j > 2 { break; }\n      n++;\n    }\n  }\n  cout n;\n}",
+        "",
+    );
+
+    assert_eq!(result.output, "6\n");
+}
+
+#[test]
+fn input_is_read_word_by_word_across_lines() {
+    let result = run_source(
+        "main() {\n  int a, b;\n  double c;\n  cin a;\n  read b;\n  cin c;\n  b = a + b;\n  write b;\n  cout c;\n}",
+        "4 5\n\n2.5\n",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "9\n2.5\n");
+}
+
+#[test]
+fn bad_or_missing_input_stops_the_program() {
+    let source = "main() {\n  int a;\n  cout 1;\n  cin a;\n  cout a;\n}";
+
+    let invalid = run_source(source, "abc");
+    assert_eq!(invalid.output, "1\n");
+    assert_eq!(invalid.exit_value, None);
+    assert_eq!(invalid.errors[0].code, codes::INVALID_INPUT);
+    assert_eq!(invalid.errors[0].primary_span.line, 4);
+
+    let missing = run_source(source, "");
+    assert_eq!(missing.errors[0].code, codes::END_OF_INPUT);
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let result = run_source(
+        "main() {\n  int x = 0;\n  cin x;\n  if x != 0 && 10 / x > 1 { cout 1; } else { cout 2; }\n  if x == 0 || 10 / x > 1 { cout 3; }\n}",
+        "0",
+    );
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.output, "2\n3\n");
+}
+
+#[test]
+fn return_sets_the_exit_value_and_runtime_errors_point_at_the_divisor() {
+    let returned = run_source("main() {\n  int x = 2;\n  return x * 21;\n  cout x;\n}", "");
+    assert_eq!(returned.output, "");
+    assert_eq!(returned.exit_value, Some(Value::Int(42)));
+
+    let failed = run_source("main() {\n  int x, y = 1;\n  cin x;\n  cout y / x;\n}", "0");
+    assert_eq!(failed.exit_value, None);
+    assert_eq!(failed.errors.len(), 1);
+    assert_eq!(failed.errors[0].code, codes::RUNTIME_DIVISION_BY_ZERO);
+    assert_eq!(failed.errors[0].labels[0].span.col_start, 12);
+}
+
+#[test]
+fn stream_console_waits_for_each_line_on_another_thread() {
+    let tree = compile("main() {\n  int a, b;\n  cout 1;\n  cin a;\n  cin b;\n  cout a * b;\n}").0;
+    let (input, receiver) = mpsc::channel();
+    let (events, received) = mpsc::channel();
+
+    let program = thread::spawn(move || {
+        let mut console = StreamConsole::new(receiver, move |event| {
+            events.send(event).unwrap();
+        });
+        Interpreter::new(&mut console).run(&tree).ok()
+    });
+    assert_eq!(
+        received.recv().unwrap(),
+        ConsoleEvent::Output("1\n".to_string())
+    );
+    assert_eq!(received.recv().unwrap(), ConsoleEvent::InputRequested);
+    input.send("6".to_string()).unwrap();
+    assert_eq!(received.recv().unwrap(), ConsoleEvent::InputRequested);
+    input.send("7".to_string()).unwrap();
+    assert_eq!(
+        received.recv().unwrap(),
+        ConsoleEvent::Output("42\n".to_string())
+    );
+    assert_eq!(program.join().unwrap(), Some(Value::Int(0)));
+}
+
+#[test]
+fn closing_the_input_channel_ends_a_waiting_program() {
+    let tree = compile("main() {\n  int a;\n  cin a;\n  cout a;\n}").0;
+    let (input, receiver) = mpsc::channel::<String>();
+    drop(input);
+
+    let mut console = StreamConsole::new(receiver, |_| {});
+    let result = Interpreter::new(&mut console).run(&tree);
+    assert_eq!(result.unwrap_err().code, codes::END_OF_INPUT);
+}
+
+#[test]
+fn step_budget_stops_an_infinite_loop_inside_it() {
+    let limits = Limits {
+        max_steps: 1000,
+        ..Limits::default()
+    };
+    let result = run(&compile(INFINITE_LOOP).0, "", limits);
+
+    assert_eq!(result.exit_value, None);
+    assert_eq!(result.errors[0].code, codes::STEP_LIMIT_EXCEEDED);
+    assert!((3..=4).contains(&result.errors[0].primary_span.line));
+    assert!(result.errors[0].help.is_some());
+}
+
+#[test]
+fn step_budget_stops_a_loop_on_an_int_condition() {
+    let limits = Limits {
+        max_steps: 1000,
+        ..Limits::default()
+    };
+    let result = run(
+        &compile("main() {\n  int x = 0;\n  while 1 { x++; }\n}").0,
+        "",
+        limits,
+    );
+
+    assert_eq!(result.exit_value, None);
+    assert_eq!(result.errors[0].code, codes::STEP_LIMIT_EXCEEDED);
+    assert_eq!(result.errors[0].primary_span.line, 3);
+}
+
+#[test]
+fn wall_clock_timeout_stops_an_infinite_loop() {
+    let limits = Limits {
+        max_steps: u64::MAX,
+        timeout_ms: 20,
+    };
+    let result = run(&compile(INFINITE_LOOP).0, "", limits);
+
+    assert_eq!(result.errors[0].code, codes::TIME_LIMIT_EXCEEDED);
+}
+
+#[test]
+fn cancelling_reports_the_line_that_was_running() {
+    let tree = compile(INFINITE_LOOP).0;
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let flag = cancel.clone();
+    let program = thread::spawn(move || {
+        let mut console = BufferedConsole::new("");
+        let limits = Limits {
+            max_steps: u64::MAX,
+            timeout_ms: u64::MAX,
+        };
+        Interpreter::new(&mut console)
+            .with_limits(limits)
+            .with_cancel(flag)
+            .run(&tree)
+            .unwrap_err()
+    });
+    thread::sleep(Duration::from_millis(20));
+    cancel.store(true, Ordering::Relaxed);
+    let error = program.join().unwrap();
+
+    assert_eq!(error.code, codes::RUN_CANCELLED);
+    assert!(error.message.contains(&error.primary_span.line.to_string()));
+}
+
+#[test]
+fn cancelling_while_waiting_for_input_is_not_an_input_error() {
+    let tree = compile("main() {\n  int a;\n  cin a;\n  cout a;\n}").0;
+    let (input, receiver) = mpsc::channel::<String>();
+    let (events, received) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let flag = cancel.clone();
+    let program = thread::spawn(move || {
+        let mut console = StreamConsole::new(receiver, move |event| {
+            events.send(event).unwrap();
+        });
+        Interpreter::new(&mut console)
+            .with_cancel(flag)
+            .run(&tree)
+            .unwrap_err()
+    });
+    assert_eq!(received.recv().unwrap(), ConsoleEvent::InputRequested);
+    cancel.store(true, Ordering::Relaxed);
+    drop(input);
+    let error = program.join().unwrap();
+
+    assert_eq!(error.code, codes::RUN_CANCELLED);
+    assert_eq!(error.primary_span.line, 3);
+}
+
+#[test]
+fn then_and_else_branches_count_the_same_steps() {
+    let then_branch = compile("main() { if true { cout 1; } else { } }").0;
+    let else_branch = compile("main() { if false { } else { cout 1; } }").0;
+
+    for max_steps in [3, 4] {
+        let limits = Limits {
+            max_steps,
+            ..Limits::default()
+        };
+        let finished = |tree: &TreeNode| run(tree, "", limits).exit_value.is_some();
+        assert_eq!(
+            finished(&then_branch),
+            finished(&else_branch),
+            "{}",
+            max_steps
+        );
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use app::ir::{generate, IntermediateCode, Op};
+use common::compile;
+
+fn lower(source: &str) -> IntermediateCode {
+    generate(&compile(source).0)
+}
+
+// Renglones del texto sin la sangría
+fn lines(code: &IntermediateCode) -> Vec<String> {
+    code.to_text()
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+#[test]
+fn expressions_use_one_temporary_per_operator() {
+    let code =
+        lower("main() {\n  int a, b, c;\n  cin a;\n  cin b;\n  c = a * b + -a;\n  cout c;\n}");
+
+    assert_eq!(
+        lines(&code),
+        [
+            "a = 0",
+            "b = 0",
+            "c = 0",
+            "read a",
+            "read b",
+            "t1 = a * b",
+            "t2 = -a",
+            "t3 = t1 + t2",
+            "c = t3",
+            "write c",
+            "halt",
+        ]
+    );
+}
+
+#[test]
+fn division_records_whether_it_is_int_or_double() {
+    let code = lower(
+        "main() {\n  int a;\n  double d;\n  cin a;\n  d = a / 2;\n  d = a / 2.0;\n  cout d;\n}",
+    );
+
+    assert_eq!(
+        lines(&code),
+        [
+            "a = 0",
+            "d = 0.0",
+            "read a",
+            "t1 = a /i 2",
+            "d = t1",
+            "t2 = a /d 2.0",
+            "d = t2",
+            "write d",
+            "halt",
+        ]
+    );
+    assert_eq!(code.quadruples[3].op, Op::DivInt);
+    assert_eq!(code.quadruples[5].op, Op::DivDouble);
+}
+
+#[test]
+fn constants_are_folded_and_converted_to_the_variable_type() {
+    let code = lower("main() {\n  int a = 2 * 3;\n  double d = a + 1;\n  cout d;\n}");
+
+    assert_eq!(lines(&code), ["a = 6", "d = 7.0", "write 7.0", "halt"]);
+}
+
+#[test]
+fn if_else_and_while_jump_over_their_bodies() {
+    let code = lower(
+        "main() {\n  int x;\n  cin x;\n  while x > 0 {\n    if x % 2 == 0 { cout 0; } else { cout 1; }\n    x--;\n  }\n}",
+    );
+
+    assert_eq!(
+        lines(&code),
+        [
+            "x = 0",
+            "read x",
+            "L1:",
+            "t1 = x > 0",
+            "if_false t1 goto L2",
+            "t2 = x % 2",
+            "t3 = t2 == 0",
+            "if_false t3 goto L4",
+            "write 0",
+            "goto L3",
+            "L4:",
+            "write 1",
+            "L3:",
+            "x = x - 1",
+            "goto L1",
+            "L2:",
+            "halt",
+        ]
+    );
+}
+
+#[test]
+fn break_and_switch_jump_to_the_end_and_cases_fall_through() {
+    let code = lower(
+        "main() {\n  int x;\n  cin x;\n  switch x {\n    case 1: cout 10;\n    case 2: cout 20; break;\n    default: cout 0;\n  }\n}",
+    );
+
+    assert_eq!(
+        lines(&code),
+        [
+            "x = 0",
+            "read x",
+            "t1 = x == 1",
+            "if_true t1 goto L2",
+            "t2 = x == 2",
+            "if_true t2 goto L3",
+            "goto L4",
+            "L2:",
+            "write 10",
+            "L3:",
+            "write 20",
+            "goto L1",
+            "L4:",
+            "write 0",
+            "L1:",
+            "halt",
+        ]
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit_with_jumps() {
+    let code = lower("main() {\n  int a;\n  cin a;\n  if a > 0 && a < 10 { cout a; }\n}");
+
+    assert_eq!(
+        lines(&code),
+        [
+            "a = 0",
+            "read a",
+            "t2 = a > 0",
+            "t1 = t2",
+            "if_false t1 goto L1",
+            "t3 = a < 10",
+            "t1 = t3",
+            "L1:",
+            "if_false t1 goto L2",
+            "write a",
+            "L2:",
+            "halt",
+        ]
+    );
+}
+
+#[test]
+fn shadowed_variables_get_their_own_name_and_json_keeps_the_table() {
+    let code = lower(
+        "main() {\n  int x;\n  cin x;\n  if true {\n    int x;\n    cin x;\n    cout x;\n  }\n  cout x;\n}",
+    );
+
+    assert_eq!(
+        lines(&code)[3..8],
+        ["x.2 = 0", "read x.2", "write x.2", "L1:", "write x"]
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&code.to_json().unwrap()).unwrap();
+    assert_eq!(json[0]["op"], "=");
+    assert_eq!(json[0]["arg1"], "0");
+    assert_eq!(json[0]["result"], "x");
+    assert_eq!(code.quadruples.last().unwrap().op, Op::Halt);
+}
+
+#[test]
+fn unary_plus_keeps_the_operand() {
+    let code = lower("main() {\n  int x, y;\n  cin x;\n  y = +x;\n  cout y;\n  y = -(+x);\n}");
+
+    assert_eq!(
+        lines(&code),
+        ["x = 0", "y = 0", "read x", "y = x", "write y", "t1 = -x", "y = t1", "halt",]
+    );
+}
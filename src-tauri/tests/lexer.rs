@@ -0,0 +1,213 @@
+use app::globals::{codes, Diagnostic, Diagnostics, Token, TokenType};
+use app::lexer::get_token;
+use std::time::{Duration, Instant};
+
+fn lex(source: &str) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut diagnostics = Diagnostics::new();
+    let tokens = get_token(source, &mut diagnostics);
+    (tokens, diagnostics.into_vec())
+}
+
+#[test]
+fn tokens_after_non_ascii_comment_keep_their_byte_offsets() {
+    let source = "// comentario: año, canción\nx = 1;";
+    let (tokens, errors) = lex(source);
+
+    assert!(errors.is_empty());
+    let x = &tokens[0];
+    assert_eq!(x.kind, TokenType::ID);
+    assert_eq!(&source[x.span.start_byte..x.span.end_byte], "x");
+    let one = &tokens[2];
+    assert_eq!(one.lexeme, "1");
+    assert_eq!(&source[one.span.start_byte..one.span.end_byte], "1");
+    assert_eq!(tokens.last().unwrap().kind, TokenType::ENDFILE);
+}
+
+#[test]
+fn multi_line_comment_with_accents_is_skipped_entirely() {
+    let source = "/* número mágico: ñ */ int n;";
+    let (tokens, errors) = lex(source);
+
+    assert!(errors.is_empty());
+    let kinds: Vec<TokenType> = tokens.iter().map(|t| t.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenType::INTEGER,
+            TokenType::ID,
+            TokenType::SEMICOLON,
+            TokenType::ENDFILE
+        ]
+    );
+}
+
+#[test]
+fn non_ascii_identifiers_are_single_tokens() {
+    let source = "int año, peñasco;";
+    let (tokens, errors) = lex(source);
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens[1].kind, TokenType::ID);
+    assert_eq!(tokens[1].lexeme, "año");
+    assert_eq!(tokens[1].span.col_start, 5);
+    assert_eq!(tokens[1].span.col_end, 8);
+    assert_eq!(tokens[3].lexeme, "peñasco");
+    assert_eq!(
+        &source[tokens[3].span.start_byte..tokens[3].span.end_byte],
+        "peñasco"
+    );
+}
+
+#[test]
+fn unknown_multi_byte_character_is_reported_once() {
+    let (tokens, errors) = lex("x ¿ y");
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::UNEXPECTED_CHARACTER);
+    assert_eq!(errors[0].message, "carácter inesperado '¿'");
+    let span = &errors[0].primary_span;
+    assert_eq!(span.end_byte - span.start_byte, 2);
+    assert_eq!(tokens.len(), 3);
+}
+
+#[test]
+fn identifier_at_end_of_file_is_not_repeated() {
+    let (tokens, _) = lex("abc");
+
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].lexeme, "abc");
+    assert_eq!(tokens[1].kind, TokenType::ENDFILE);
+}
+
+#[test]
+fn one_megabyte_source_scans_in_linear_time() {
+    let line = "x = x + 12.5; // acción\n";
+    let source = line.repeat(1024 * 1024 / line.len());
+
+    let started = Instant::now();
+    let (tokens, errors) = lex(&source);
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), 6 * (1024 * 1024 / line.len()) + 1);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn comments_only_advance_lines_on_real_newlines() {
+    let source = "// uno\n/* dos\n * tres */ x\ny /* * */ z";
+    let (tokens, _) = lex(source);
+
+    let positions: Vec<(String, usize, usize)> = tokens
+        .iter()
+        .map(|t| (t.lexeme.clone(), t.span.line, t.span.col_start))
+        .collect();
+    assert_eq!(positions[0], ("x".to_string(), 3, 12));
+    assert_eq!(positions[1], ("y".to_string(), 4, 1));
+    assert_eq!(positions[2], ("z".to_string(), 4, 11));
+}
+
+#[test]
+fn crlf_counts_as_a_single_line_break() {
+    let (tokens, _) = lex("a\r\nb\r\n\r\n  c");
+
+    assert_eq!(tokens[0].span.line, 1);
+    assert_eq!(tokens[1].span.line, 2);
+    assert_eq!(tokens[1].span.col_start, 1);
+    assert_eq!(tokens[2].span.line, 4);
+    assert_eq!(tokens[2].span.col_start, 3);
+}
+
+#[test]
+fn lone_bang_is_a_not_token() {
+    let (tokens, errors) = lex("!a != b");
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens[0].kind, TokenType::NOT);
+    assert_eq!(tokens[2].kind, TokenType::NEQ);
+}
+
+#[test]
+fn logical_operators_need_two_characters() {
+    let (tokens, errors) = lex("a && b || c & d | e");
+
+    assert_eq!(tokens[1].kind, TokenType::AND);
+    assert_eq!(tokens[3].kind, TokenType::OR);
+    let columns: Vec<usize> = errors.iter().map(|d| d.primary_span.col_start).collect();
+    assert_eq!(columns, vec![13, 17]);
+    assert!(errors.iter().all(|d| d.help.is_some()));
+}
+
+#[test]
+fn true_and_false_are_reserved_words() {
+    let (tokens, _) = lex("true false truth");
+
+    assert_eq!(tokens[0].kind, TokenType::TRUE);
+    assert_eq!(tokens[1].kind, TokenType::FALSE);
+    assert_eq!(tokens[2].kind, TokenType::ID);
+}
+
+#[test]
+fn malformed_real_and_unclosed_comment_are_diagnostics() {
+    let (tokens, errors) = lex("x = 12.;\n/* sin cerrar");
+
+    let found: Vec<(&str, usize)> = errors
+        .iter()
+        .map(|d| (d.code.as_str(), d.primary_span.line))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::MALFORMED_NUMBER, 1),
+            (codes::UNTERMINATED_COMMENT, 2)
+        ]
+    );
+    assert_eq!(tokens.last().unwrap().kind, TokenType::ENDFILE);
+}
+
+// (bytes, línea inicial y final, columnas) del span de un token
+fn span_of(token: &Token) -> ((usize, usize), (usize, usize), (usize, usize)) {
+    let span = &token.span;
+    (
+        (span.start_byte, span.end_byte),
+        (span.line, span.end_line),
+        (span.col_start, span.col_end),
+    )
+}
+
+#[test]
+fn tokens_carry_byte_offsets_line_and_columns() {
+    let source = "int x;\n  x = 10;";
+    let (tokens, errors) = lex(source);
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(span_of(&tokens[0]), ((0, 3), (1, 1), (1, 4)));
+    assert_eq!(span_of(&tokens[1]), ((4, 5), (1, 1), (5, 6)));
+    assert_eq!(span_of(&tokens[3]), ((9, 10), (2, 2), (3, 4)));
+    assert_eq!(span_of(&tokens[5]), ((13, 15), (2, 2), (7, 9)));
+    assert_eq!(tokens[5].kind, TokenType::NumInt);
+    assert_eq!(&source[13..15], "10");
+}
+
+#[test]
+fn spans_after_a_multi_line_comment_count_bytes_and_characters_apart() {
+    let source = "/* ñandú\n   más */ int año;\naño = 1;";
+    let (tokens, errors) = lex(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    assert_eq!(span_of(&tokens[0]), ((22, 25), (2, 2), (11, 14)));
+    assert_eq!(span_of(&tokens[1]), ((26, 30), (2, 2), (15, 18)));
+    assert_eq!(span_of(&tokens[2]), ((30, 31), (2, 2), (18, 19)));
+    assert_eq!(span_of(&tokens[3]), ((32, 36), (3, 3), (1, 4)));
+    assert_eq!(&source[26..30], "año");
+}
+
+#[test]
+fn unterminated_comment_points_at_its_opening() {
+    let (_, errors) = lex("x\n  /* ñ\n sin cerrar");
+
+    assert_eq!(errors.len(), 1);
+    let span = &errors[0].primary_span;
+    assert_eq!((span.start_byte, span.end_byte), (4, 6));
+    assert_eq!((span.line, span.end_line), (2, 2));
+    assert_eq!((span.col_start, span.col_end), (3, 5));
+}
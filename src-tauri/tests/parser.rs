@@ -0,0 +1,369 @@
+mod common;
+
+use app::globals::{codes, Diagnostic, Diagnostics, NodeType, TokenType, TreeNode};
+use app::lexer::get_token;
+use app::parser::parse_program;
+use common::parse;
+
+// Parsea `x = <expression>;` dentro de main y devuelve el lado derecho de la asignación
+fn parse_assigned_expression(expression: &str) -> TreeNode {
+    let tree = parse(&format!("main() {{ x = {}; }}", expression));
+
+    let block = &tree.children[0].children[0];
+    let assignment = &block.children[0];
+    assert_eq!(assignment.node_type, NodeType::Assignment);
+    assignment.children[1].clone()
+}
+
+// Representa el árbol de una expresión con paréntesis explícitos
+fn shape(node: &TreeNode) -> String {
+    match node.children.len() {
+        0 => node.value.clone().unwrap_or_default(),
+        1 => shape(&node.children[0]),
+        2 => format!(
+            "({}{})",
+            node.children[0].value.clone().unwrap_or_default(),
+            shape(&node.children[1])
+        ),
+        _ => format!(
+            "({} {} {})",
+            shape(&node.children[0]),
+            node.children[1].value.clone().unwrap_or_default(),
+            shape(&node.children[2])
+        ),
+    }
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_eq!(
+        shape(&parse_assigned_expression("a + b * c")),
+        "(a + (b * c))"
+    );
+    assert_eq!(
+        shape(&parse_assigned_expression("a * b + c")),
+        "((a * b) + c)"
+    );
+}
+
+#[test]
+fn arithmetic_binds_tighter_than_comparison_and_logic() {
+    let tree = parse_assigned_expression("a + b < c * d && e");
+    assert_eq!(shape(&tree), "(((a + b) < (c * d)) && e)");
+}
+
+#[test]
+fn logical_or_has_the_lowest_precedence() {
+    let tree = parse_assigned_expression("a || b && c == d");
+    assert_eq!(shape(&tree), "(a || (b && (c == d)))");
+}
+
+#[test]
+fn equality_is_below_relational() {
+    let tree = parse_assigned_expression("a < b == c >= d");
+    assert_eq!(shape(&tree), "((a < b) == (c >= d))");
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!(
+        shape(&parse_assigned_expression("a - b - c")),
+        "((a - b) - c)"
+    );
+    assert_eq!(
+        shape(&parse_assigned_expression("a / b % c")),
+        "((a / b) % c)"
+    );
+}
+
+#[test]
+fn power_is_right_associative_and_binds_tightest() {
+    assert_eq!(
+        shape(&parse_assigned_expression("2 ^ 3 ^ 2")),
+        "(2 ^ (3 ^ 2))"
+    );
+    assert_eq!(
+        shape(&parse_assigned_expression("a * b ^ c")),
+        "(a * (b ^ c))"
+    );
+}
+
+#[test]
+fn parentheses_override_precedence() {
+    assert_eq!(
+        shape(&parse_assigned_expression("(a + b) * c")),
+        "((a + b) * c)"
+    );
+}
+
+#[test]
+fn multiplicative_nodes_are_terms() {
+    let tree = parse_assigned_expression("a + b * c");
+    assert_eq!(tree.node_type, NodeType::Expression);
+    assert_eq!(tree.children[2].node_type, NodeType::Term);
+}
+
+#[test]
+fn unary_minus_binds_looser_than_power() {
+    let tree = parse_assigned_expression("-2 ^ 2");
+    assert_eq!(tree.node_type, NodeType::UnaryExpression);
+    assert_eq!(shape(&tree), "(-(2 ^ 2))");
+}
+
+#[test]
+fn unary_operators_bind_tighter_than_binary_ones() {
+    assert_eq!(shape(&parse_assigned_expression("-a * b")), "((-a) * b)");
+    assert_eq!(shape(&parse_assigned_expression("a - -b")), "(a - (-b))");
+    assert_eq!(shape(&parse_assigned_expression("!(a < b)")), "(!(a < b))");
+    assert_eq!(shape(&parse_assigned_expression("+a")), "(+a)");
+}
+
+#[test]
+fn power_exponent_may_be_signed() {
+    assert_eq!(shape(&parse_assigned_expression("2 ^ -1")), "(2 ^ (-1))");
+}
+
+#[test]
+fn boolean_literals_are_factors() {
+    let tree = parse_assigned_expression("true || !false");
+    assert_eq!(shape(&tree), "(true || (!false))");
+}
+
+fn parse_with_errors(source: &str) -> (TreeNode, Vec<Diagnostic>) {
+    let mut diagnostics = Diagnostics::new();
+    let tokens = get_token(source, &mut diagnostics);
+    let tree = parse_program(&tokens, &mut 0, &mut diagnostics).unwrap();
+    (tree, diagnostics.into_vec())
+}
+
+#[test]
+fn missing_operand_is_reported_once_and_parsing_resumes() {
+    let (tree, errors) = parse_with_errors("main() { x = ; y = 2; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children.len(), 1);
+    assert_eq!(block.children[0].children[0].value.as_deref(), Some("y"));
+}
+
+#[test]
+fn missing_assign_is_reported_once() {
+    assert_eq!(parse_with_errors("main() { x; }").1.len(), 1);
+
+    let (tree, errors) = parse_with_errors("main() { x; y = 2; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::EXPECTED_TOKEN);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children.len(), 1);
+    assert_eq!(block.children[0].children[0].value.as_deref(), Some("y"));
+}
+
+#[test]
+fn broken_statement_skips_its_nested_block() {
+    let (tree, errors) = parse_with_errors("main() { if ) { y = 1; } z = 3; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children.len(), 1);
+    assert_eq!(block.children[0].children[0].value.as_deref(), Some("z"));
+}
+
+#[test]
+fn recovery_stops_at_the_next_statement_keyword() {
+    let (tree, errors) = parse_with_errors("main() { x = 1 + * 2 while x { x = 0; } }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children[0].node_type, NodeType::WhileStatement);
+}
+
+#[test]
+fn stray_tokens_always_make_progress() {
+    let (_, errors) = parse_with_errors(") ) } + main() { }");
+
+    assert!(!errors.is_empty());
+}
+
+#[test]
+fn bare_expression_does_not_consume_the_following_statement() {
+    let (tree, errors) = parse_with_errors("main() { 1 + 2; x = 3; }");
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::EXPRESSION_WITHOUT_ASSIGNMENT);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children[0].children[0].value.as_deref(), Some("x"));
+}
+
+#[test]
+fn nodes_cover_the_source_they_were_parsed_from() {
+    let source = "main() {\n  if a < 10 {\n    x = (a + 1) * 2;\n  }\n}";
+    let (tree, errors) = parse_with_errors(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let text = |node: &TreeNode| &source[node.span.start_byte..node.span.end_byte];
+    let if_node = &tree.children[0].children[0].children[0];
+    assert_eq!(if_node.node_type, NodeType::IfStatement);
+    assert_eq!((if_node.span.line, if_node.span.end_line), (2, 4));
+    assert_eq!(text(&if_node.children[0]), "a < 10");
+
+    let assignment = &if_node.children[1].children[0];
+    assert_eq!(text(assignment), "x = (a + 1) * 2");
+    assert_eq!(text(&assignment.children[1]), "(a + 1) * 2");
+    assert_eq!(text(&assignment.children[1].children[0]), "(a + 1)");
+    assert_eq!(assignment.children[1].children[1].span.col_start, 17);
+}
+
+#[test]
+fn declarations_hold_one_declarator_per_variable() {
+    let (tree, errors) = parse_with_errors("main() { double a, b = 2.5 * a, c; }");
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let declaration = &tree.children[0].children[0].children[0];
+    assert_eq!(declaration.node_type, NodeType::DoubleStatement);
+    let declarators: Vec<(Option<&str>, usize)> = declaration
+        .children
+        .iter()
+        .map(|d| (d.value.as_deref(), d.children.len()))
+        .collect();
+    assert_eq!(
+        declarators,
+        vec![(Some("a"), 0), (Some("b"), 1), (Some("c"), 0)]
+    );
+    assert!(declaration
+        .children
+        .iter()
+        .all(|d| d.node_type == NodeType::Declarator));
+    assert_eq!(shape(&declaration.children[1].children[0]), "(2.5 * a)");
+}
+
+#[test]
+fn blocks_may_be_empty_or_nested() {
+    let source = "main() {\n  if a { }\n  while a { if a { x = 1; y = 2; } else { } x = 3; }\n}";
+    let tree = parse(source);
+
+    let body = &tree.children[0].children[0];
+    assert_eq!(body.node_type, NodeType::Block);
+    assert_eq!(body.children.len(), 2);
+
+    let empty = &body.children[0].children[1];
+    assert_eq!(empty.node_type, NodeType::Block);
+    assert!(empty.children.is_empty());
+    assert_eq!(&source[empty.span.start_byte..empty.span.end_byte], "{ }");
+
+    let loop_body = &body.children[1].children[1];
+    assert_eq!(loop_body.children.len(), 2);
+    let inner = &loop_body.children[0];
+    assert_eq!(inner.children[1].node_type, NodeType::Block);
+    assert_eq!(inner.children[1].children.len(), 2);
+    assert!(inner.children[2].children[0].children.is_empty());
+}
+
+#[test]
+fn switch_holds_its_selector_cases_and_default() {
+    let tree = parse("main() { switch x { case 1: y = 1; break; case 2: default: y = 0; } }");
+
+    let switch = &tree.children[0].children[0].children[0];
+    assert_eq!(switch.node_type, NodeType::SwitchStatement);
+    assert_eq!(switch.children[0].value.as_deref(), Some("x"));
+    let arms: Vec<(&NodeType, usize)> = switch.children[1..]
+        .iter()
+        .map(|arm| (&arm.node_type, arm.children.last().unwrap().children.len()))
+        .collect();
+    assert_eq!(
+        arms,
+        vec![
+            (&NodeType::CaseStatement, 2),
+            (&NodeType::CaseStatement, 0),
+            (&NodeType::DefaultStatement, 1)
+        ]
+    );
+    assert_eq!(switch.children[1].children[0].value.as_deref(), Some("1"));
+    assert_eq!(
+        switch.children[1].children[1].children[1].node_type,
+        NodeType::BreakStatement
+    );
+}
+
+#[test]
+fn for_loop_holds_init_condition_update_and_body() {
+    let tree = parse("main() { for (i = 0; i < 3; i++) { x = i; } }");
+
+    let for_node = &tree.children[0].children[0].children[0];
+    assert_eq!(for_node.node_type, NodeType::ForStatement);
+    let kinds: Vec<&NodeType> = for_node.children.iter().map(|c| &c.node_type).collect();
+    assert_eq!(kinds[0], &NodeType::Assignment);
+    assert_eq!(kinds[2], &NodeType::Increment);
+    assert_eq!(kinds[3], &NodeType::Block);
+    assert_eq!(for_node.children[3].children.len(), 1);
+}
+
+#[test]
+fn empty_for_condition_is_true() {
+    let tree = parse("main() { for (;;) { break; } }");
+
+    let for_node = &tree.children[0].children[0].children[0];
+    assert_eq!(for_node.children.len(), 4);
+    assert_eq!(for_node.children[1].token, Some(TokenType::TRUE));
+    assert_eq!(for_node.children[1].value.as_deref(), Some("true"));
+    assert_eq!(for_node.children[1].span.col_start, 16);
+}
+
+#[test]
+fn for_init_and_update_may_be_empty() {
+    let source = "main() { for (; i < 3;) { i++; } }";
+    let tree = parse(source);
+
+    let for_node = &tree.children[0].children[0].children[0];
+    assert_eq!(for_node.children.len(), 4);
+    assert_eq!(for_node.children[0].node_type, NodeType::Block);
+    assert!(for_node.children[0].children.is_empty());
+    assert_eq!(for_node.children[2].node_type, NodeType::Block);
+    assert!(for_node.children[2].children.is_empty());
+    let text = |node: &TreeNode| &source[node.span.start_byte..node.span.end_byte];
+    assert_eq!(text(&for_node.children[0]), ";");
+    assert_eq!(text(&for_node.children[2]), ")");
+}
+
+#[test]
+fn for_init_may_declare_variables() {
+    let source = "main() { for (double d = 0.5, e; d < 3; d++) { } }";
+    let tree = parse(source);
+
+    let for_node = &tree.children[0].children[0].children[0];
+    let init = &for_node.children[0];
+    assert_eq!(init.node_type, NodeType::DoubleStatement);
+    let names: Vec<&str> = init
+        .children
+        .iter()
+        .map(|declarator| declarator.value.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, vec!["d", "e"]);
+    assert_eq!(
+        &source[init.span.start_byte..init.span.end_byte],
+        "double d = 0.5, e"
+    );
+    assert_eq!(for_node.children[3].node_type, NodeType::Block);
+}
+
+#[test]
+fn case_outside_a_switch_is_reported_once() {
+    let (tree, errors) = parse_with_errors("main() { case 1: x = 1; y = 2; }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::OUTSIDE_SWITCH);
+    let block = &tree.children[0].children[0];
+    assert_eq!(block.children.len(), 1);
+    assert_eq!(block.children[0].children[0].value.as_deref(), Some("y"));
+}
+
+#[test]
+fn stray_tokens_inside_a_switch_are_skipped_up_to_the_next_label() {
+    let (tree, errors) = parse_with_errors("main() { switch x { y = 1; case 1: y = 2; } }");
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, codes::EXPECTED_TOKEN);
+    let switch = &tree.children[0].children[0].children[0];
+    assert_eq!(switch.children.len(), 2);
+    assert_eq!(switch.children[1].node_type, NodeType::CaseStatement);
+}
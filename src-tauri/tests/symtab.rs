@@ -0,0 +1,89 @@
+use app::symTab::{ScopeKind, SymbolTable};
+
+#[test]
+fn colliding_names_get_their_own_entries() {
+    // "aq" y "ba" caen en la misma posición de la tabla hash
+    let mut table = SymbolTable::new();
+    table.insert("aq", "int", "0", 1, 0);
+    table.insert("ba", "double", "0", 2, 1);
+    table.add_line("aq", 3);
+
+    let aq = table.lookup("aq").unwrap();
+    assert_eq!((aq._type.as_str(), aq.memloc), ("int", 0));
+    assert_eq!(aq.declaration_line, 1);
+    assert_eq!(
+        aq.lines.iter().map(|l| l.lineno).collect::<Vec<_>>(),
+        vec![3]
+    );
+    let ba = table.lookup("ba").unwrap();
+    assert_eq!((ba._type.as_str(), ba.memloc), ("double", 1));
+    assert_eq!(ba.declaration_line, 2);
+    assert!(ba.lines.is_empty());
+}
+
+#[test]
+fn lookups_walk_outward_and_inner_declarations_shadow() {
+    let mut table = SymbolTable::new();
+    table.insert("x", "int", "0", 1, 0);
+    table.enter_scope(ScopeKind::Block);
+    assert_eq!(table.lookup("x").unwrap().memloc, 0);
+    assert!(table.lookup_current("x").is_none());
+
+    table.insert("x", "double", "0", 3, 1);
+    assert_eq!(table.lookup("x").unwrap()._type, "double");
+    table.exit_scope();
+
+    assert_eq!(table.lookup("x").unwrap()._type, "int");
+}
+
+#[test]
+fn block_locals_are_invisible_after_the_block_but_kept() {
+    let mut table = SymbolTable::new();
+    table.enter_scope(ScopeKind::Function);
+    table.enter_scope(ScopeKind::Block);
+    table.insert("tmp", "int", "0", 2, 0);
+    let block = table.current_scope();
+    table.exit_scope();
+
+    assert!(table.lookup("tmp").is_none());
+    assert_eq!(table.scope_kind(table.current_scope()), ScopeKind::Function);
+    let symbols = table.symbols();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].scope, block);
+}
+
+#[test]
+fn entries_split_declaration_and_use_lines() {
+    let mut table = SymbolTable::new();
+    table.insert("x", "int", "0", 2, 0);
+    table.add_line("x", 2);
+    table.add_line("x", 4);
+    table.add_line("x", 4);
+    table.add_line("x", 7);
+    table.enter_scope(ScopeKind::Block);
+    table.insert("y", "double", "0", 5, 1);
+
+    let entries: Vec<_> = table.entries().collect();
+    assert_eq!(entries[0].declaration_line, 2);
+    assert_eq!(entries[0].use_lines, vec![2, 4, 7]);
+    assert_eq!(entries[1].scope_kind, ScopeKind::Block);
+}
+
+#[test]
+fn csv_and_json_exports_have_one_row_per_symbol() {
+    let mut table = SymbolTable::new();
+    table.insert("x", "int", "0", 2, 0);
+    table.add_line("x", 4);
+    table.add_line("x", 7);
+    table.insert("y", "double", "1,5", 3, 1);
+
+    assert_eq!(
+        table.to_csv(),
+        "name,type,value,memloc,declaration_line,use_lines,scope,scope_kind\n\
+         x,int,0,0,2,4 7,0,Global\n\
+         y,double,\"1,5\",1,3,,0,Global\n"
+    );
+    let json: serde_json::Value = serde_json::from_str(&table.to_json().unwrap()).unwrap();
+    assert_eq!(json[1]["type"], "double");
+    assert_eq!(json[0]["use_lines"], serde_json::json!([4, 7]));
+}
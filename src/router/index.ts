@@ -8,6 +8,8 @@ import Lexic from "../views/Lexic.vue";
 import Semantic from "../views/Semantic.vue";
 import Syntax from "../views/Syntax.vue";
 import Run from "../views/Run.vue";
+import Debug from "../views/Debug.vue";
+import Intermediate from "../views/Intermediate.vue";
 
 const router = createRouter({
   history: createWebHistory(import.meta.env.BASE_URL),
@@ -52,6 +54,16 @@ const router = createRouter({
               name: "run",
               component: Run,
             },
+            {
+              path: "debug",
+              name: "debug",
+              component: Debug,
+            },
+            {
+              path: "intermediate",
+              name: "intermediate",
+              component: Intermediate,
+            },
           ],
         },
       ],
@@ -1,6 +1,48 @@
 import { ref, computed } from "vue";
 import { defineStore } from "pinia";
 
+// Posición de un token en el código fuente (columnas 1-based, col_end exclusiva)
+export interface Span {
+  start_byte: number;
+  end_byte: number;
+  line: number;
+  end_line: number;
+  col_start: number;
+  col_end: number;
+}
+
+// Fila de la tabla de símbolos
+export interface SymbolRecord {
+  name: string;
+  type: string;
+  value: string;
+  memloc: number;
+  declaration_line: number;
+  use_lines: number[];
+  scope: number;
+  scope_kind: "Global" | "Function" | "Block" | "For";
+}
+
+// Token tal como lo serializa el escáner
+export interface Token {
+  kind: string;
+  lexeme: string;
+  span: Span;
+}
+
+export type Severity = "Error" | "Warning" | "Note";
+
+// Diagnóstico producido por el escáner, el parser o el análisis semántico
+export interface Diagnostic {
+  code: string;
+  severity: Severity;
+  message: string;
+  primary_span: Span;
+  labels: { span: Span; message: string }[];
+  notes: string[];
+  help: string | null;
+}
+
 export const useStore = defineStore({
   id: "main",
   state: () => ({
@@ -13,9 +55,15 @@ export const useStore = defineStore({
     row: 0,
     flagEditor: false,
     flagSave: false,
-    errors: [] as string[][],
-    errorsSyntax: [] as string[],
-    tokens: [] as string[][],
+    errors: [] as Diagnostic[],
+    errorsSyntax: [] as Diagnostic[],
+    errorsSemantic: [] as Diagnostic[],
+    tokens: [] as Token[],
+    selection: null as Span | null,
+    // Se incrementa cada vez que se pide ejecutar el programa en la terminal
+    runRequest: 0,
+    // Líneas (1-based) con breakpoint para el depurador
+    breakpoints: [] as number[],
   }),
   actions: {
     toggleSidebar() {
@@ -43,18 +91,39 @@ export const useStore = defineStore({
     setFlagSave(flag: boolean) {
       this.flagSave = flag;
     },
-    setErrors(errors: string[][]) {
+    setErrors(errors: Diagnostic[]) {
       this.errors = errors;
     },
-    setTokens(tokens: string[][]) {
+    setTokens(tokens: Token[]) {
       this.tokens = tokens;
     },
-    setErrorsSyntax(errors: string[]) {
+    setErrorsSyntax(errors: Diagnostic[]) {
       this.errorsSyntax = errors;
     },
+    setErrorsSemantic(errors: Diagnostic[]) {
+      this.errorsSemantic = errors;
+    },
+    // Pide al editor que seleccione un fragmento del código
+    setSelection(span: Span | null) {
+      this.selection = span;
+    },
+    // Pide a la terminal que compile y ejecute el código del editor
+    requestRun() {
+      this.runRequest++;
+    },
+    // Pone o quita el breakpoint de una línea
+    toggleBreakpoint(line: number) {
+      const index = this.breakpoints.indexOf(line);
+      if (index === -1) {
+        this.breakpoints.push(line);
+      } else {
+        this.breakpoints.splice(index, 1);
+      }
+    },
     resetErrors() {
       this.errors = [];
       this.errorsSyntax = [];
+      this.errorsSemantic = [];
     },
   },
 });